@@ -1,11 +1,12 @@
 use cosmwasm_schema::write_api;
 
-use cw404::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use cw404::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
 
 fn main() {
     write_api! {
         instantiate: InstantiateMsg,
         execute: ExecuteMsg,
         query: QueryMsg,
+        migrate: MigrateMsg,
     }
 }
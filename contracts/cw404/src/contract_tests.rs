@@ -0,0 +1,1543 @@
+#![cfg(test)]
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info, MockApi, MockQuerier};
+use cosmwasm_std::{
+    coin, coins, from_json, Addr, Binary, Coin, Deps, DepsMut, Empty, Env, MemoryStorage,
+    MessageInfo, OwnedDeps, Response, StdResult, Storage, Uint128,
+};
+use cw2::VersionError;
+use cw20::{
+    AllAllowancesResponse, AllSpenderAllowancesResponse, AllowanceResponse, BalanceResponse,
+    DownloadLogoResponse, EmbeddedLogo, Logo, LogoInfo, MarketingInfoResponse, TokenInfoResponse,
+};
+use cw721::{AllNftInfoResponse, Cw721ReceiveMsg, OwnerOfResponse, TokensResponse};
+use cw_multi_test::{App, AppResponse, Contract, ContractWrapper, Executor};
+use cw_ownable::OwnershipError;
+use cw_utils::Expiration;
+use serde::de::DeserializeOwned;
+
+use crate::contract::{execute, instantiate, migrate, query, CONTRACT_NAME, CONTRACT_VERSION};
+use crate::msg::{
+    BurnedTokensResponse, InstantiateMarketingInfo, MigrateMsg, MinterResponse,
+    SupplyStatsResponse, TokenHistoryResponse,
+};
+use crate::state::{
+    BurnOrder, Extension, RarityTier, RerollFee, ACQUIRED, ACQUIRED_COUNT, ALLOWANCE_OWNERS,
+    BURN_ORDER, BURN_RANK, DECIMALS, HISTORY_LIMIT, LOCKED, LOGO_SIZE_CAP, MIGRATING_TOKENS,
+    MINTED, OWNED, OWNED_INDEX, OWNER, OWNER_OF, RARITY_TOTAL_WEIGHT, TOTAL_SUPPLY,
+};
+use crate::{ContractError, ExecuteMsg, InstantiateMsg, QueryMsg};
+
+const CREATOR: &str = "creator";
+const ALICE: &str = "alice";
+const BOBBY: &str = "bobby";
+const DENOM: &str = "uatom";
+
+fn cw404_contract() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(execute, instantiate, query).with_migrate(migrate))
+}
+
+#[cw_serde]
+enum ReceiverMsg {
+    ReceiveNft(Cw721ReceiveMsg),
+}
+
+fn receiver_execute(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: ReceiverMsg,
+) -> StdResult<Response> {
+    let ReceiverMsg::ReceiveNft(msg) = msg;
+    Ok(Response::new().add_attribute("received", msg.token_id))
+}
+
+fn receiver_instantiate(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _msg: Empty,
+) -> StdResult<Response> {
+    Ok(Response::new())
+}
+
+fn receiver_query(_deps: Deps, _env: Env, _msg: Empty) -> StdResult<Binary> {
+    Ok(Binary::default())
+}
+
+/// A contract accepting any `ReceiveNft`, reporting each token id as a `received` attribute
+fn receiver_contract() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(
+        receiver_execute,
+        receiver_instantiate,
+        receiver_query,
+    ))
+}
+
+/// Values of the `key` attributes set by the contract at `contract`
+fn wasm_attrs(res: &AppResponse, contract: &Addr, key: &str) -> Vec<String> {
+    res.events
+        .iter()
+        .filter(|event| event.ty == "wasm")
+        .filter(|event| {
+            let address = event
+                .attributes
+                .iter()
+                .find(|a| a.key == "_contract_address");
+            address.map_or(false, |a| a.value == contract.as_str())
+        })
+        .flat_map(|event| event.attributes.iter())
+        .filter(|attr| attr.key == key)
+        .map(|attr| attr.value.clone())
+        .collect()
+}
+
+fn instantiate_msg(total_native_supply: u128, recycle_ids: bool) -> InstantiateMsg {
+    InstantiateMsg {
+        name: "cw404".to_string(),
+        symbol: "CW404".to_string(),
+        decimals: 0,
+        total_native_supply: Uint128::new(total_native_supply),
+        initial_native_supply: None,
+        minter: None,
+        recycle_ids: Some(recycle_ids),
+        burn_order: None,
+        marketing: None,
+    }
+}
+
+/// A collection with one unit per NFT by default, its whole supply held by the whitelisted creator.
+/// Alice also holds native coins to pay fees with
+struct Suite {
+    app: App,
+    contract: Addr,
+}
+
+impl Suite {
+    fn new(total_native_supply: u128) -> Self {
+        Self::with_recycling(total_native_supply, false)
+    }
+
+    fn with_recycling(total_native_supply: u128, recycle_ids: bool) -> Self {
+        Self::with_msg(instantiate_msg(total_native_supply, recycle_ids))
+    }
+
+    fn with_msg(msg: InstantiateMsg) -> Self {
+        let mut app = App::new(|router, _, storage| {
+            router
+                .bank
+                .init_balance(storage, &Addr::unchecked(ALICE), coins(1_000, DENOM))
+                .unwrap();
+        });
+        let code_id = app.store_code(cw404_contract());
+        let contract = app
+            .instantiate_contract(
+                code_id,
+                Addr::unchecked(CREATOR),
+                &msg,
+                &[],
+                "cw404",
+                Some(CREATOR.to_string()),
+            )
+            .unwrap();
+
+        let mut suite = Suite { app, contract };
+        suite
+            .execute(
+                CREATOR,
+                ExecuteMsg::SetWhitelist {
+                    target: CREATOR.to_string(),
+                    state: true,
+                },
+            )
+            .unwrap();
+        suite
+    }
+
+    fn execute(&mut self, sender: &str, msg: ExecuteMsg) -> Result<AppResponse, ContractError> {
+        self.execute_with_funds(sender, msg, &[])
+    }
+
+    fn execute_with_funds(
+        &mut self,
+        sender: &str,
+        msg: ExecuteMsg,
+        funds: &[Coin],
+    ) -> Result<AppResponse, ContractError> {
+        self.app
+            .execute_contract(Addr::unchecked(sender), self.contract.clone(), &msg, funds)
+            .map_err(|err| err.downcast().unwrap())
+    }
+
+    fn query<T: DeserializeOwned>(&self, msg: QueryMsg) -> T {
+        self.app
+            .wrap()
+            .query_wasm_smart(self.contract.clone(), &msg)
+            .unwrap()
+    }
+
+    fn transfer(
+        &mut self,
+        from: &str,
+        to: &str,
+        amount: u128,
+    ) -> Result<AppResponse, ContractError> {
+        let msg = ExecuteMsg::Transfer {
+            recipient: to.to_string(),
+            amount: Uint128::new(amount),
+        };
+        self.execute(from, msg)
+    }
+
+    /// Migrates the contract to its own code with `msg`
+    fn migrate(&mut self, msg: MigrateMsg) {
+        let code_id = self
+            .app
+            .wrap()
+            .query_wasm_contract_info(self.contract.clone())
+            .unwrap()
+            .code_id;
+        self.app
+            .migrate_contract(
+                Addr::unchecked(CREATOR),
+                self.contract.clone(),
+                &msg,
+                code_id,
+            )
+            .unwrap();
+    }
+
+    fn height(&self) -> u64 {
+        self.app.block_info().height
+    }
+
+    fn advance(&mut self, blocks: u64) {
+        self.app.update_block(|block| {
+            block.height += blocks;
+            block.time = block.time.plus_seconds(5 * blocks);
+        });
+    }
+
+    fn balance(&self, address: &str) -> u128 {
+        let res: BalanceResponse = self.query(QueryMsg::Balance {
+            address: address.to_string(),
+        });
+        res.balance.u128()
+    }
+
+    fn tokens(&self, owner: &str) -> Vec<Uint128> {
+        let res: TokensResponse = self.query(QueryMsg::Tokens {
+            owner: owner.to_string(),
+            start_after: None,
+            limit: Some(1000),
+        });
+        res.tokens.iter().map(|id| id.parse().unwrap()).collect()
+    }
+
+    fn next_to_burn(&self, address: &str) -> Vec<Uint128> {
+        let res: TokensResponse = self.query(QueryMsg::NextToBurn {
+            address: address.to_string(),
+            count: 10,
+        });
+        res.tokens.iter().map(|id| id.parse().unwrap()).collect()
+    }
+
+    /// Sends fractions of a token from Alice to Bobby until Alice holds no NFT, returning the
+    /// NFTs burnt in order
+    fn burn_alice_nfts(&mut self) -> Vec<Uint128> {
+        let mut burnt = vec![];
+        // 50 units first, so every later transfer of 100 crosses a whole token
+        let mut amount = 50;
+        while !self.tokens(ALICE).is_empty() {
+            let before = self.tokens(ALICE);
+            self.transfer(ALICE, BOBBY, amount).unwrap();
+            let after = self.tokens(ALICE);
+            let lost: Vec<_> = before
+                .into_iter()
+                .filter(|id| !after.contains(id))
+                .collect();
+            assert_eq!(lost.len(), 1);
+            burnt.extend(lost);
+            amount = 100;
+        }
+        burnt
+    }
+}
+
+#[test]
+fn reconciling_sender_burns_like_a_holder() {
+    let mut suite = Suite::new(1000);
+    suite.transfer(CREATOR, ALICE, 150).unwrap();
+
+    // Leaving the whitelist mints the first batch of 100 NFTs
+    let exempt = |state| ExecuteMsg::SetSelfNftExempt { state };
+    suite.execute(ALICE, exempt(true)).unwrap();
+    suite.execute(ALICE, exempt(false)).unwrap();
+    let owned = suite.tokens(ALICE);
+    assert_eq!(owned.len(), 100);
+
+    // Locked NFTs cannot be left behind by sending the balance away
+    let lock = |state| ExecuteMsg::SetLocks {
+        token_ids: owned.clone(),
+        state,
+        until: None,
+    };
+    suite.execute(ALICE, lock(true)).unwrap();
+    let err = suite.transfer(ALICE, BOBBY, 150).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::AllTokensLocked {
+            address: ALICE.to_string()
+        }
+    );
+
+    suite.execute(ALICE, lock(false)).unwrap();
+    suite.transfer(ALICE, BOBBY, 150).unwrap();
+    assert!(suite.tokens(ALICE).is_empty());
+    assert_eq!(suite.tokens(BOBBY).len(), 150);
+
+    // Nothing is left to mint, so alice leaves the whitelist
+    let msg = ExecuteMsg::ReconcileNfts {
+        address: ALICE.to_string(),
+    };
+    suite.execute(BOBBY, msg).unwrap();
+    let exempt: bool = suite.query(QueryMsg::IsNftExempt {
+        address: ALICE.to_string(),
+    });
+    assert!(!exempt);
+
+    let stats: SupplyStatsResponse = suite.query(QueryMsg::SupplyStats {});
+    assert_eq!(stats.nft_supply, 150);
+}
+
+#[test]
+fn opting_back_in_is_free() {
+    let mut suite = Suite::new(1000);
+    suite.transfer(CREATOR, ALICE, 3).unwrap();
+    let msg = ExecuteMsg::SetRerollFee {
+        fee: Some(coin(10, DENOM)),
+        recipient: Some(CREATOR.to_string()),
+    };
+    suite.execute(CREATOR, msg).unwrap();
+
+    let exempt = |state| ExecuteMsg::SetSelfNftExempt { state };
+    suite.execute(ALICE, exempt(true)).unwrap();
+    assert!(suite.tokens(ALICE).is_empty());
+    suite.execute(ALICE, exempt(false)).unwrap();
+    assert_eq!(suite.tokens(ALICE).len(), 3);
+}
+
+#[test]
+fn exempt_balance_follows_whitelisted_balances() {
+    let mut suite = Suite::new(1000);
+    let exempt_balance = |suite: &Suite| {
+        let stats: SupplyStatsResponse = suite.query(QueryMsg::SupplyStats {});
+        stats.exempt_balance.u128()
+    };
+    assert_eq!(exempt_balance(&suite), 1000);
+
+    suite.transfer(CREATOR, ALICE, 150).unwrap();
+    assert_eq!(exempt_balance(&suite), 850);
+
+    // Alice's balance counts while exempt
+    let exempt = |state| ExecuteMsg::SetSelfNftExempt { state };
+    suite.execute(ALICE, exempt(true)).unwrap();
+    assert_eq!(exempt_balance(&suite), 1000);
+    suite.transfer(ALICE, BOBBY, 50).unwrap();
+    let msg = ExecuteMsg::Burn {
+        amount: Uint128::new(10),
+    };
+    suite.execute(ALICE, msg).unwrap();
+    assert_eq!(exempt_balance(&suite), 940);
+
+    suite.execute(ALICE, exempt(false)).unwrap();
+    assert_eq!(exempt_balance(&suite), 850);
+}
+
+#[test]
+fn reroll_never_hands_back_the_same_id() {
+    let mut suite = Suite::with_recycling(3, true);
+    suite.transfer(CREATOR, ALICE, 3).unwrap();
+    assert_eq!(suite.tokens(ALICE), [1u128, 2, 3].map(Uint128::new));
+
+    let reroll = |token_id: Uint128| ExecuteMsg::Reroll { token_id };
+    let err = suite.execute(ALICE, reroll(Uint128::new(2))).unwrap_err();
+    assert_eq!(err, ContractError::TokenIdsExhausted {});
+
+    // A burnt id is handed out and the rerolled one queued after it
+    let msg = ExecuteMsg::Burn {
+        amount: Uint128::one(),
+    };
+    suite.execute(ALICE, msg).unwrap();
+    let owned = suite.tokens(ALICE);
+    let burnt = [1u128, 2, 3]
+        .map(Uint128::new)
+        .into_iter()
+        .find(|id| !owned.contains(id))
+        .unwrap();
+    suite.execute(ALICE, reroll(owned[0])).unwrap();
+    let rerolled = suite.tokens(ALICE);
+    assert!(rerolled.contains(&burnt));
+    assert!(!rerolled.contains(&owned[0]));
+}
+
+#[test]
+fn zero_reroll_fee_leaves_rerolls_free() {
+    let mut suite = Suite::new(1000);
+    suite.transfer(CREATOR, ALICE, 1).unwrap();
+    let msg = ExecuteMsg::SetRerollFee {
+        fee: Some(coin(0, DENOM)),
+        recipient: Some(CREATOR.to_string()),
+    };
+    suite.execute(CREATOR, msg).unwrap();
+    let fee: Option<RerollFee> = suite.query(QueryMsg::RerollFee {});
+    assert_eq!(fee, None);
+
+    let token_id = suite.tokens(ALICE)[0];
+    suite
+        .execute(ALICE, ExecuteMsg::Reroll { token_id })
+        .unwrap();
+    assert_ne!(suite.tokens(ALICE), [token_id]);
+}
+
+/// Stores ownership the way deployments did before `tokens()`: alice holds more NFTs than
+/// a single migration step moves, some acquired before acquisitions were tracked, two
+/// listed by `SetBurnOrder` and two locked. Bobby holds a few and carol an emptied list
+fn legacy_deps(order: Option<BurnOrder>) -> OwnedDeps<MemoryStorage, MockApi, MockQuerier> {
+    let mut deps = mock_dependencies();
+    let storage = deps.as_mut().storage;
+    DECIMALS.save(storage, &0).unwrap();
+    TOTAL_SUPPLY.save(storage, &Uint128::new(1000)).unwrap();
+    MINTED.save(storage, &Uint128::new(160)).unwrap();
+    OWNER.save(storage, &CREATOR.to_string()).unwrap();
+    ACQUIRED_COUNT.save(storage, &1000).unwrap();
+    if let Some(order) = order {
+        BURN_ORDER.save(storage, &order).unwrap();
+    }
+
+    let alice: Vec<u128> = (1..=150).rev().collect();
+    let bobby = vec![153, 151, 155, 152, 154];
+    for (owner, owned) in [(ALICE, alice), (BOBBY, bobby), ("carol", vec![])] {
+        for (index, id) in owned.iter().enumerate() {
+            OWNER_OF
+                .save(storage, id.to_string(), &owner.to_string())
+                .unwrap();
+            OWNED_INDEX
+                .save(storage, id.to_string(), &Uint128::from(index as u128))
+                .unwrap();
+            if owner == ALICE && id % 3 == 0 {
+                ACQUIRED
+                    .save(storage, id.to_string(), &(900 - *id as u64))
+                    .unwrap();
+            }
+        }
+        let owned = owned.into_iter().map(Uint128::new).collect();
+        OWNED.save(storage, owner.to_string(), &owned).unwrap();
+    }
+    BURN_RANK.save(storage, "20".to_string(), &0).unwrap();
+    BURN_RANK.save(storage, "10".to_string(), &1).unwrap();
+    LOCKED.save(storage, "42".to_string(), &true).unwrap();
+    LOCKED.save(storage, "3".to_string(), &true).unwrap();
+    deps
+}
+
+/// The burn queue of `owner` as deployments computed it from the owned lists
+fn legacy_burn_queue(storage: &dyn Storage, owner: &str) -> Vec<String> {
+    let order = BURN_ORDER.may_load(storage).unwrap().unwrap_or_default();
+    let owned = OWNED.load(storage, owner.to_string()).unwrap();
+    let mut queue = vec![];
+    for (position, id) in owned.into_iter().enumerate() {
+        if LOCKED.has(storage, id.to_string()) {
+            continue;
+        }
+        let rank = BURN_RANK.may_load(storage, id.to_string()).unwrap();
+        let acquired = ACQUIRED.may_load(storage, id.to_string()).unwrap();
+        queue.push((rank, (acquired.unwrap_or_default(), position), id));
+    }
+    queue.sort_by(|a, b| {
+        a.0.cmp(&b.0).then_with(|| match order {
+            BurnOrder::Lifo => b.1.cmp(&a.1),
+            BurnOrder::Fifo => a.1.cmp(&b.1),
+            BurnOrder::LowestId => a.2.cmp(&b.2),
+            BurnOrder::HighestId => b.2.cmp(&a.2),
+        })
+    });
+    queue.into_iter().map(|(_, _, id)| id.to_string()).collect()
+}
+
+fn query_mock<T: DeserializeOwned>(
+    deps: &OwnedDeps<MemoryStorage, MockApi, MockQuerier>,
+    msg: QueryMsg,
+) -> T {
+    from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap()
+}
+
+fn migrates_owned_lists_in_batches(order: Option<BurnOrder>) {
+    let mut deps = legacy_deps(order);
+    let holders = [ALICE, BOBBY];
+    let before: Vec<_> = holders
+        .iter()
+        .map(|owner| {
+            let storage = deps.as_ref().storage;
+            let mut owned = OWNED.load(storage, owner.to_string()).unwrap();
+            owned.sort();
+            let owned: Vec<_> = owned.iter().map(|id| id.to_string()).collect();
+            (owned, legacy_burn_queue(storage, owner))
+        })
+        .collect();
+
+    let msg = MigrateMsg {
+        base_token_uri: None,
+        recycle_ids: None,
+        legacy_nft_events: None,
+    };
+    migrate(deps.as_mut(), mock_env(), msg).unwrap();
+    assert!(MIGRATING_TOKENS.exists(deps.as_ref().storage));
+
+    // Nothing else runs until every NFT is moved
+    let transfer = ExecuteMsg::Transfer {
+        recipient: BOBBY.to_string(),
+        amount: Uint128::one(),
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info(ALICE, &[]), transfer).unwrap_err();
+    assert_eq!(err, ContractError::MigrationPending {});
+
+    let info = mock_info(CREATOR, &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::MigrateTokens {},
+    )
+    .unwrap();
+    assert!(res
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "done" && attr.value == "true"));
+    assert!(!MIGRATING_TOKENS.exists(deps.as_ref().storage));
+    assert!(OWNED.is_empty(deps.as_ref().storage));
+    assert!(OWNER_OF.is_empty(deps.as_ref().storage));
+
+    for (owner, (tokens, next_to_burn)) in holders.iter().zip(before) {
+        let res: TokensResponse = query_mock(
+            &deps,
+            QueryMsg::Tokens {
+                owner: owner.to_string(),
+                start_after: None,
+                limit: Some(1000),
+            },
+        );
+        assert_eq!(res.tokens, tokens);
+        let res: TokensResponse = query_mock(
+            &deps,
+            QueryMsg::NextToBurn {
+                address: owner.to_string(),
+                count: 1000,
+            },
+        );
+        assert_eq!(res.tokens, next_to_burn);
+    }
+
+    let res: TokensResponse = query_mock(
+        &deps,
+        QueryMsg::LockedTokens {
+            owner: ALICE.to_string(),
+            start_after: None,
+            limit: None,
+        },
+    );
+    assert_eq!(res.tokens, ["3", "42"]);
+
+    let stats: SupplyStatsResponse = query_mock(&deps, QueryMsg::SupplyStats {});
+    assert_eq!(stats.nft_supply, 155);
+    assert_eq!(stats.nfts_burned, 5);
+    assert_eq!(stats.nft_holders, 2);
+}
+
+#[test]
+fn migrates_owned_lists_in_lifo_order() {
+    migrates_owned_lists_in_batches(None);
+}
+
+#[test]
+fn migrates_owned_lists_in_fifo_order() {
+    migrates_owned_lists_in_batches(Some(BurnOrder::Fifo));
+}
+
+#[test]
+fn enabling_recycling_retires_ids_above_the_supply() {
+    let mut suite = Suite::new(3);
+
+    // Every transfer burns the NFT sent and mints a fresh one, up to id 4
+    suite.transfer(CREATOR, ALICE, 1).unwrap();
+    suite.transfer(ALICE, BOBBY, 1).unwrap();
+    suite.transfer(BOBBY, ALICE, 1).unwrap();
+    suite.transfer(ALICE, BOBBY, 1).unwrap();
+    assert_eq!(suite.tokens(BOBBY), [Uint128::new(4)]);
+
+    let msg = MigrateMsg {
+        base_token_uri: None,
+        recycle_ids: Some(true),
+        legacy_nft_events: None,
+    };
+    suite.migrate(msg);
+
+    suite.transfer(BOBBY, ALICE, 1).unwrap();
+    assert_eq!(suite.tokens(ALICE), [Uint128::new(1)]);
+    suite.transfer(ALICE, BOBBY, 1).unwrap();
+    assert_eq!(suite.tokens(BOBBY), [Uint128::new(2)]);
+    suite.transfer(BOBBY, ALICE, 1).unwrap();
+    suite.transfer(ALICE, BOBBY, 1).unwrap();
+    suite.transfer(BOBBY, ALICE, 1).unwrap();
+    assert_eq!(suite.tokens(ALICE), [Uint128::new(2)]);
+}
+
+#[test]
+fn migration_indexes_allowances_by_spender() {
+    let mut deps = mock_dependencies();
+    let info = mock_info(CREATOR, &[]);
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        instantiate_msg(1000, false),
+    )
+    .unwrap();
+    let msg = ExecuteMsg::IncreaseAllowance {
+        spender: BOBBY.to_string(),
+        amount: Uint128::new(10),
+        expires: None,
+    };
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // A release that tracked the cw2 version but not the allowances by spender
+    let storage = deps.as_mut().storage;
+    ALLOWANCE_OWNERS.remove(storage, (BOBBY.to_string(), CREATOR.to_string()));
+    cw2::set_contract_version(storage, CONTRACT_NAME, "0.18.0").unwrap();
+    let msg = MigrateMsg {
+        base_token_uri: None,
+        recycle_ids: None,
+        legacy_nft_events: None,
+    };
+    migrate(deps.as_mut(), mock_env(), msg).unwrap();
+
+    let res: AllSpenderAllowancesResponse = query_mock(
+        &deps,
+        QueryMsg::AllSpenderAllowances {
+            spender: BOBBY.to_string(),
+            start_after: None,
+            limit: None,
+        },
+    );
+    assert_eq!(res.allowances.len(), 1);
+    assert_eq!(res.allowances[0].owner, CREATOR);
+}
+
+#[test]
+fn locked_tokens_follow_their_owner() {
+    let mut suite = Suite::new(1000);
+    suite.transfer(CREATOR, ALICE, 5).unwrap();
+    let owned = suite.tokens(ALICE);
+    let locked = |suite: &Suite, owner: &str| {
+        let res: TokensResponse = suite.query(QueryMsg::LockedTokens {
+            owner: owner.to_string(),
+            start_after: None,
+            limit: None,
+        });
+        res.tokens
+    };
+
+    let msg = ExecuteMsg::SetLocks {
+        token_ids: vec![owned[1], owned[3]],
+        state: true,
+        until: None,
+    };
+    suite.execute(ALICE, msg).unwrap();
+    assert_eq!(
+        locked(&suite, ALICE),
+        [owned[1].to_string(), owned[3].to_string()]
+    );
+
+    // The recipient of a locked token starts unlocked
+    let msg = ExecuteMsg::TransferNft {
+        recipient: BOBBY.to_string(),
+        token_id: owned[3],
+    };
+    suite.execute(ALICE, msg).unwrap();
+    assert_eq!(locked(&suite, ALICE), [owned[1].to_string()]);
+    assert!(locked(&suite, BOBBY).is_empty());
+
+    let msg = ExecuteMsg::SetLock {
+        token_id: owned[1],
+        state: false,
+    };
+    suite.execute(ALICE, msg).unwrap();
+    assert!(locked(&suite, ALICE).is_empty());
+}
+
+#[test]
+fn rarity_weights_that_overflow_are_rejected() {
+    let mut suite = Suite::new(1000);
+    let tier = |name: &str, weight| RarityTier {
+        name: name.to_string(),
+        weight,
+    };
+    let msg = ExecuteMsg::SetRarityTiers {
+        tiers: vec![tier("common", u64::MAX), tier("rare", 2)],
+    };
+    let err = suite.execute(CREATOR, msg).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidRarityTiers {
+            total: RARITY_TOTAL_WEIGHT
+        }
+    );
+}
+
+#[test]
+fn transfers_to_self_keep_the_nfts() {
+    let mut suite = Suite::new(1000);
+    suite.transfer(CREATOR, ALICE, 3).unwrap();
+    let owned = suite.tokens(ALICE);
+
+    let res = suite.transfer(ALICE, ALICE, 1).unwrap();
+    assert!(!res.events.iter().any(|e| e.ty == "wasm-cw721_burn"));
+    let msg = ExecuteMsg::MultiTransfer {
+        transfers: vec![(ALICE.to_string(), Uint128::new(2))],
+    };
+    suite.execute(ALICE, msg).unwrap();
+
+    assert_eq!(suite.balance(ALICE), 3);
+    assert_eq!(suite.tokens(ALICE), owned);
+}
+
+#[test]
+fn already_expired_approvals_are_rejected() {
+    let mut suite = Suite::new(1000);
+    suite.transfer(CREATOR, ALICE, 1).unwrap();
+    let token_id = suite.tokens(ALICE)[0];
+    let expired = Some(Expiration::AtHeight(suite.height()));
+
+    let msg = ExecuteMsg::Approve {
+        spender: BOBBY.to_string(),
+        token_id,
+        expires: expired,
+    };
+    assert_eq!(
+        suite.execute(ALICE, msg).unwrap_err(),
+        ContractError::Expired {}
+    );
+    let msg = ExecuteMsg::ApproveAll {
+        operator: BOBBY.to_string(),
+        expires: expired,
+    };
+    assert_eq!(
+        suite.execute(ALICE, msg).unwrap_err(),
+        ContractError::Expired {}
+    );
+    let msg = ExecuteMsg::IncreaseAllowance {
+        spender: BOBBY.to_string(),
+        amount: Uint128::one(),
+        expires: expired,
+    };
+    assert_eq!(
+        suite.execute(ALICE, msg).unwrap_err(),
+        ContractError::Expired {}
+    );
+}
+
+#[test]
+fn expired_approvals_stop_transfers() {
+    let mut suite = Suite::new(1000);
+    suite.transfer(CREATOR, ALICE, 2).unwrap();
+    let owned = suite.tokens(ALICE);
+    let expires = Some(Expiration::AtHeight(suite.height() + 10));
+
+    let msg = ExecuteMsg::Approve {
+        spender: BOBBY.to_string(),
+        token_id: owned[0],
+        expires,
+    };
+    suite.execute(ALICE, msg).unwrap();
+    let msg = ExecuteMsg::ApproveAll {
+        operator: "carol".to_string(),
+        expires,
+    };
+    suite.execute(ALICE, msg).unwrap();
+
+    let approvals = |suite: &Suite, include_expired| {
+        let token_id = owned[0].to_string();
+        let res: OwnerOfResponse = suite.query(QueryMsg::OwnerOf {
+            token_id: token_id.clone(),
+            include_expired: Some(include_expired),
+        });
+        let info: AllNftInfoResponse<Extension> = suite.query(QueryMsg::AllNftInfo {
+            token_id,
+            include_expired: Some(include_expired),
+        });
+        assert_eq!(res, info.access);
+        res.approvals.len()
+    };
+    assert_eq!(approvals(&suite, false), 1);
+
+    suite.advance(10);
+    assert_eq!(approvals(&suite, false), 0);
+    assert_eq!(approvals(&suite, true), 1);
+
+    let transfer = |sender: &str, token_id| ExecuteMsg::TransferNftFrom {
+        owner: ALICE.to_string(),
+        recipient: sender.to_string(),
+        token_id,
+    };
+    let err = suite.execute(BOBBY, transfer(BOBBY, owned[0])).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let err = suite
+        .execute("carol", transfer("carol", owned[1]))
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+}
+
+#[test]
+fn expired_allowances_stop_spending() {
+    let mut suite = Suite::new(1000);
+    suite.transfer(CREATOR, ALICE, 10).unwrap();
+    let msg = ExecuteMsg::IncreaseAllowance {
+        spender: BOBBY.to_string(),
+        amount: Uint128::new(10),
+        expires: Some(Expiration::AtHeight(suite.height() + 5)),
+    };
+    suite.execute(ALICE, msg).unwrap();
+
+    let transfer_from = ExecuteMsg::TransferFrom {
+        owner: ALICE.to_string(),
+        recipient: BOBBY.to_string(),
+        amount: Uint128::one(),
+    };
+    let burn_from = ExecuteMsg::BurnFrom {
+        owner: ALICE.to_string(),
+        amount: Uint128::one(),
+    };
+    suite.execute(BOBBY, transfer_from.clone()).unwrap();
+    suite.execute(BOBBY, burn_from.clone()).unwrap();
+
+    suite.advance(5);
+    let err = suite.execute(BOBBY, transfer_from).unwrap_err();
+    assert_eq!(err, ContractError::Expired {});
+    let err = suite.execute(BOBBY, burn_from).unwrap_err();
+    assert_eq!(err, ContractError::Expired {});
+    assert_eq!(suite.balance(ALICE), 8);
+}
+
+#[test]
+fn allowances_follow_cw20_base() {
+    let mut suite = Suite::new(1000);
+    let allowance = |amount, increase| {
+        let spender = BOBBY.to_string();
+        let amount = Uint128::new(amount);
+        if increase {
+            ExecuteMsg::IncreaseAllowance {
+                spender,
+                amount,
+                expires: None,
+            }
+        } else {
+            ExecuteMsg::DecreaseAllowance {
+                spender,
+                amount,
+                expires: None,
+            }
+        }
+    };
+    let query = |suite: &Suite| {
+        let res: AllowanceResponse = suite.query(QueryMsg::Allowance {
+            owner: CREATOR.to_string(),
+            spender: BOBBY.to_string(),
+        });
+        res.allowance.u128()
+    };
+
+    // Increases add up, decreases clamp at zero and drop the allowance
+    suite.execute(CREATOR, allowance(10, true)).unwrap();
+    suite.execute(CREATOR, allowance(5, true)).unwrap();
+    assert_eq!(query(&suite), 15);
+    suite.execute(CREATOR, allowance(4, false)).unwrap();
+    assert_eq!(query(&suite), 11);
+    suite.execute(CREATOR, allowance(100, false)).unwrap();
+    assert_eq!(query(&suite), 0);
+    let res: AllAllowancesResponse = suite.query(QueryMsg::AllAllowances {
+        owner: CREATOR.to_string(),
+        start_after: None,
+        limit: None,
+    });
+    assert!(res.allowances.is_empty());
+
+    let msg = ExecuteMsg::IncreaseAllowance {
+        spender: CREATOR.to_string(),
+        amount: Uint128::one(),
+        expires: None,
+    };
+    let err = suite.execute(CREATOR, msg).unwrap_err();
+    assert_eq!(err, ContractError::CannotSetOwnAccount {});
+}
+
+#[test]
+fn revoking_another_spender_is_refused() {
+    let mut suite = Suite::new(1000);
+    suite.transfer(CREATOR, ALICE, 1).unwrap();
+    let token_id = suite.tokens(ALICE)[0];
+    let msg = ExecuteMsg::Approve {
+        spender: BOBBY.to_string(),
+        token_id,
+        expires: None,
+    };
+    suite.execute(ALICE, msg).unwrap();
+
+    let revoke = |spender: &str| ExecuteMsg::Revoke {
+        spender: spender.to_string(),
+        token_id,
+    };
+    let err = suite.execute(ALICE, revoke("carol")).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::ApprovalNotFound {
+            spender: "carol".to_string()
+        }
+    );
+    suite.execute(ALICE, revoke(BOBBY)).unwrap();
+    let res: OwnerOfResponse = suite.query(QueryMsg::OwnerOf {
+        token_id: token_id.to_string(),
+        include_expired: None,
+    });
+    assert!(res.approvals.is_empty());
+}
+
+#[test]
+fn burns_lower_the_supply_and_the_nfts() {
+    let mut msg = instantiate_msg(10, false);
+    msg.decimals = 2;
+    let mut suite = Suite::with_msg(msg);
+    suite.transfer(CREATOR, ALICE, 300).unwrap();
+    assert_eq!(suite.tokens(ALICE).len(), 3);
+    let total_supply = |suite: &Suite| {
+        let res: TokenInfoResponse = suite.query(QueryMsg::TokenInfo {});
+        res.total_supply.u128()
+    };
+
+    // Dropping below a whole token burns its NFT
+    let msg = ExecuteMsg::Burn {
+        amount: Uint128::new(50),
+    };
+    suite.execute(ALICE, msg).unwrap();
+    assert_eq!(suite.tokens(ALICE).len(), 2);
+    assert_eq!(total_supply(&suite), 950);
+
+    let msg = ExecuteMsg::IncreaseAllowance {
+        spender: BOBBY.to_string(),
+        amount: Uint128::new(100),
+        expires: None,
+    };
+    suite.execute(ALICE, msg).unwrap();
+    let burn_from = |amount| ExecuteMsg::BurnFrom {
+        owner: ALICE.to_string(),
+        amount: Uint128::new(amount),
+    };
+    suite.execute(BOBBY, burn_from(100)).unwrap();
+    assert!(suite.execute(BOBBY, burn_from(1)).is_err());
+    let res: AllowanceResponse = suite.query(QueryMsg::Allowance {
+        owner: ALICE.to_string(),
+        spender: BOBBY.to_string(),
+    });
+    assert!(res.allowance.is_zero());
+    assert_eq!(suite.balance(ALICE), 150);
+    assert_eq!(suite.tokens(ALICE).len(), 1);
+    assert_eq!(total_supply(&suite), 850);
+
+    // A locked NFT cannot be burnt directly
+    let token_id = suite.tokens(ALICE)[0];
+    let lock = |state| ExecuteMsg::SetLock { token_id, state };
+    suite.execute(ALICE, lock(true)).unwrap();
+    let err = suite
+        .execute(ALICE, ExecuteMsg::BurnNft { token_id })
+        .unwrap_err();
+    assert_eq!(err, ContractError::PreventBurn {});
+
+    suite.execute(ALICE, lock(false)).unwrap();
+    suite
+        .execute(ALICE, ExecuteMsg::BurnNft { token_id })
+        .unwrap();
+    assert_eq!(suite.balance(ALICE), 50);
+    assert!(suite.tokens(ALICE).is_empty());
+    assert_eq!(total_supply(&suite), 750);
+}
+
+fn migrate_from(contract: &str, version: &str) -> Result<(), ContractError> {
+    let mut deps = mock_dependencies();
+    let info = mock_info(CREATOR, &[]);
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        instantiate_msg(1000, false),
+    )
+    .unwrap();
+    cw2::set_contract_version(deps.as_mut().storage, contract, version).unwrap();
+    let msg = MigrateMsg {
+        base_token_uri: None,
+        recycle_ids: None,
+        legacy_nft_events: None,
+    };
+    migrate(deps.as_mut(), mock_env(), msg).map(|_| ())
+}
+
+#[test]
+fn migration_refuses_another_contract() {
+    let err = migrate_from("crates.io:cw20-base", "0.1.0").unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Version(VersionError::WrongContract {
+            expected: CONTRACT_NAME.to_string(),
+            found: "crates.io:cw20-base".to_string(),
+        })
+    );
+}
+
+#[test]
+fn migration_refuses_to_downgrade() {
+    let err = migrate_from(CONTRACT_NAME, "99.0.0").unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Version(VersionError::WrongVersion {
+            expected: format!("<= {CONTRACT_VERSION}"),
+            found: "99.0.0".to_string(),
+        })
+    );
+    migrate_from(CONTRACT_NAME, "0.1.0").unwrap();
+}
+
+#[test]
+fn initial_supply_cannot_exceed_the_cap() {
+    let mut deps = mock_dependencies();
+    let mut msg = instantiate_msg(10, false);
+    msg.initial_native_supply = Some(Uint128::new(11));
+    let err = instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap_err();
+    assert_eq!(err, ContractError::CannotExceedCap {});
+}
+
+#[test]
+fn minter_mints_up_to_the_cap() {
+    let mut msg = instantiate_msg(10, false);
+    msg.initial_native_supply = Some(Uint128::new(4));
+    msg.minter = Some(ALICE.to_string());
+    let mut suite = Suite::with_msg(msg);
+    let minter = |suite: &Suite| -> MinterResponse { suite.query(QueryMsg::Minter {}) };
+    let res = minter(&suite);
+    assert_eq!(res.minter.as_deref(), Some(ALICE));
+    assert_eq!(res.cap.u128(), 10);
+    assert_eq!(res.remaining.u128(), 6);
+
+    let mint = |amount| ExecuteMsg::Mint {
+        recipient: BOBBY.to_string(),
+        amount: Uint128::new(amount),
+    };
+    let err = suite.execute(BOBBY, mint(1)).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // A recipient that is not exempt gets one NFT per whole token minted
+    suite.execute(ALICE, mint(3)).unwrap();
+    assert_eq!(suite.tokens(BOBBY).len(), 3);
+    assert_eq!(minter(&suite).remaining.u128(), 3);
+    let err = suite.execute(ALICE, mint(4)).unwrap_err();
+    assert_eq!(err, ContractError::CannotExceedCap {});
+
+    // Only the owner or the current minter can replace the minter
+    let update = |new_minter: Option<&str>| ExecuteMsg::UpdateMinter {
+        new_minter: new_minter.map(str::to_string),
+    };
+    let err = suite.execute(BOBBY, update(Some(BOBBY))).unwrap_err();
+    assert_eq!(err, ContractError::Ownership(OwnershipError::NotOwner));
+    suite.execute(CREATOR, update(Some(BOBBY))).unwrap();
+    let err = suite.execute(ALICE, mint(1)).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    suite.execute(BOBBY, mint(1)).unwrap();
+    suite.execute(BOBBY, update(None)).unwrap();
+    assert_eq!(minter(&suite).minter, None);
+}
+
+#[test]
+fn batch_nft_transfers_are_all_or_nothing() {
+    let mut suite = Suite::new(1000);
+    suite.transfer(CREATOR, ALICE, 3).unwrap();
+    let owned = suite.tokens(ALICE);
+
+    let batch = |token_ids: &[Uint128]| ExecuteMsg::BatchTransferNft {
+        recipient: BOBBY.to_string(),
+        token_ids: token_ids.to_vec(),
+    };
+    let err = suite
+        .execute(ALICE, batch(&[owned[0], owned[1], Uint128::new(999)]))
+        .unwrap_err();
+    assert_eq!(err, ContractError::InvalidSender {});
+    assert_eq!(suite.tokens(ALICE), owned);
+
+    let res = suite.execute(ALICE, batch(&owned[..2])).unwrap();
+    assert_eq!(wasm_attrs(&res, &suite.contract, "amount"), ["2"]);
+    assert_eq!(suite.tokens(BOBBY), owned[..2]);
+    assert_eq!(suite.balance(ALICE), 1);
+    assert_eq!(suite.balance(BOBBY), 2);
+
+    // Every token sent reaches the receiver with its own ReceiveNft
+    let code_id = suite.app.store_code(receiver_contract());
+    let receiver = suite
+        .app
+        .instantiate_contract(
+            code_id,
+            Addr::unchecked(CREATOR),
+            &Empty {},
+            &[],
+            "receiver",
+            None,
+        )
+        .unwrap();
+    let msg = ExecuteMsg::BatchSendNft {
+        contract: receiver.to_string(),
+        token_ids: owned[..2].to_vec(),
+        msg: Binary::default(),
+    };
+    let res = suite.execute(BOBBY, msg).unwrap();
+    let received: Vec<_> = owned[..2].iter().map(Uint128::to_string).collect();
+    assert_eq!(wasm_attrs(&res, &receiver, "received"), received);
+    assert_eq!(suite.tokens(receiver.as_str()), owned[..2]);
+}
+
+#[test]
+fn multi_transfer_is_all_or_nothing() {
+    let mut suite = Suite::new(1000);
+    let multi = |alice, bobby| ExecuteMsg::MultiTransfer {
+        transfers: vec![
+            (ALICE.to_string(), Uint128::new(alice)),
+            (BOBBY.to_string(), Uint128::new(bobby)),
+        ],
+    };
+    let res = suite.execute(CREATOR, multi(2, 3)).unwrap();
+    assert_eq!(wasm_attrs(&res, &suite.contract, "amount"), ["5"]);
+    assert_eq!(suite.tokens(ALICE).len(), 2);
+    assert_eq!(suite.tokens(BOBBY).len(), 3);
+
+    assert!(suite.execute(CREATOR, multi(1, 10_000)).is_err());
+    assert_eq!(suite.balance(ALICE), 2);
+    assert_eq!(suite.tokens(ALICE).len(), 2);
+}
+
+/// Attributes of the `ty` events of `res`, in order
+fn events(res: &AppResponse, ty: &str) -> Vec<Vec<(String, String)>> {
+    res.events
+        .iter()
+        .filter(|event| event.ty == ty)
+        .map(|event| {
+            let attrs = event.attributes.iter();
+            attrs.map(|a| (a.key.clone(), a.value.clone())).collect()
+        })
+        .collect()
+}
+
+fn attrs(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+    pairs
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+#[test]
+fn nft_changes_emit_cw721_events() {
+    let mut suite = Suite::new(1000);
+    let contract = suite.contract.to_string();
+
+    let res = suite.transfer(CREATOR, ALICE, 2).unwrap();
+    let mints: Vec<_> = ["1", "2"]
+        .iter()
+        .map(|id| {
+            attrs(&[
+                ("_contract_address", &contract),
+                ("minter", &contract),
+                ("owner", ALICE),
+                ("token_id", id),
+            ])
+        })
+        .collect();
+    assert_eq!(events(&res, "wasm-cw721_mint"), mints);
+    // Nothing is dispatched to the contract itself
+    assert_eq!(events(&res, "execute").len(), 1);
+
+    let msg = ExecuteMsg::TransferNft {
+        recipient: BOBBY.to_string(),
+        token_id: Uint128::new(1),
+    };
+    let res = suite.execute(ALICE, msg).unwrap();
+    let transfer = attrs(&[
+        ("_contract_address", &contract),
+        ("sender", ALICE),
+        ("recipient", BOBBY),
+        ("token_id", "1"),
+    ]);
+    assert_eq!(events(&res, "wasm-cw721_transfer"), [transfer]);
+
+    let msg = ExecuteMsg::Burn {
+        amount: Uint128::one(),
+    };
+    let res = suite.execute(ALICE, msg).unwrap();
+    let burn = attrs(&[
+        ("_contract_address", &contract),
+        ("owner", ALICE),
+        ("token_id", "2"),
+    ]);
+    assert_eq!(events(&res, "wasm-cw721_burn"), [burn]);
+}
+
+#[test]
+fn legacy_nft_events_dispatch_generate_messages() {
+    let mut suite = Suite::new(1000);
+    suite.migrate(MigrateMsg {
+        base_token_uri: None,
+        recycle_ids: None,
+        legacy_nft_events: Some(true),
+    });
+
+    // One self-dispatched GenerateNftMintEvent per NFT, next to the inline events
+    let res = suite.transfer(CREATOR, ALICE, 2).unwrap();
+    assert_eq!(events(&res, "wasm-cw721_mint").len(), 2);
+    assert_eq!(events(&res, "execute").len(), 3);
+    let actions = wasm_attrs(&res, &suite.contract, "action");
+    assert_eq!(actions, ["transfer", "mint", "mint"]);
+
+    let msg = ExecuteMsg::GenerateNftBurnEvent {
+        sender: ALICE.to_string(),
+        token_id: Uint128::one(),
+    };
+    let err = suite.execute(ALICE, msg).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+}
+
+fn token_history(suite: &Suite, token_id: u128) -> TokenHistoryResponse {
+    suite.query(QueryMsg::TokenHistory {
+        token_id: token_id.to_string(),
+    })
+}
+
+#[test]
+fn token_history_keeps_the_latest_owners() {
+    let mut suite = Suite::new(1000);
+    suite.transfer(CREATOR, ALICE, 1).unwrap();
+    let minted_height = suite.height();
+
+    // Twelve more owners push the first two out of the history
+    let owners = [ALICE, BOBBY];
+    for i in 1..=12 {
+        suite.advance(1);
+        let msg = ExecuteMsg::TransferNft {
+            recipient: owners[i % 2].to_string(),
+            token_id: Uint128::one(),
+        };
+        suite.execute(owners[(i + 1) % 2], msg).unwrap();
+    }
+
+    let history = token_history(&suite, 1).history.unwrap();
+    assert_eq!(history.first_owner, ALICE);
+    assert_eq!(history.minted_height, minted_height);
+    assert_eq!(history.owners.len(), HISTORY_LIMIT);
+    let heights: Vec<_> = history.owners.iter().map(|o| o.height).collect();
+    let expected: Vec<_> = (minted_height + 3..=minted_height + 12).collect();
+    assert_eq!(heights, expected);
+    // The twelfth transfer hands the NFT back to Alice
+    let last = history.owners.last().unwrap();
+    assert_eq!(last.owner, ALICE);
+    assert_eq!(suite.tokens(ALICE), [Uint128::one()]);
+}
+
+#[test]
+fn recycled_ids_start_a_new_history() {
+    let mut suite = Suite::with_recycling(10, true);
+    suite.transfer(CREATOR, ALICE, 1).unwrap();
+    let msg = ExecuteMsg::Burn {
+        amount: Uint128::one(),
+    };
+    suite.execute(ALICE, msg).unwrap();
+
+    // The burnt NFT keeps its history next to the burn record
+    let res = token_history(&suite, 1);
+    assert_eq!(res.history.unwrap().first_owner, ALICE);
+    let burned = res.burned.unwrap();
+    assert_eq!(burned.last_owner, ALICE);
+    assert_eq!(burned.height, suite.height());
+
+    suite.advance(1);
+    suite.transfer(CREATOR, BOBBY, 1).unwrap();
+    assert_eq!(suite.tokens(BOBBY), [Uint128::one()]);
+    let res = token_history(&suite, 1);
+    assert_eq!(res.burned, None);
+    let history = res.history.unwrap();
+    assert_eq!(history.first_owner, BOBBY);
+    assert_eq!(history.minted_height, suite.height());
+    let owners: Vec<_> = history.owners.iter().map(|o| o.owner.as_str()).collect();
+    assert_eq!(owners, [BOBBY]);
+
+    let res: BurnedTokensResponse = suite.query(QueryMsg::BurnedTokens {
+        start_after: None,
+        limit: None,
+    });
+    assert!(res.tokens.is_empty());
+}
+
+#[test]
+fn burned_tokens_are_paged_by_id() {
+    let mut suite = Suite::new(1000);
+    suite.transfer(CREATOR, ALICE, 5).unwrap();
+    let msg = ExecuteMsg::Burn {
+        amount: Uint128::new(5),
+    };
+    suite.execute(ALICE, msg).unwrap();
+
+    let mut pages = vec![];
+    let mut start_after = None;
+    loop {
+        let res: BurnedTokensResponse = suite.query(QueryMsg::BurnedTokens {
+            start_after: start_after.clone(),
+            limit: Some(2),
+        });
+        if res.tokens.is_empty() {
+            break;
+        }
+        for token in &res.tokens {
+            assert_eq!(token.burned.last_owner, ALICE);
+        }
+        let page: Vec<_> = res.tokens.into_iter().map(|t| t.token_id).collect();
+        start_after = page.last().cloned();
+        pages.push(page);
+    }
+    assert_eq!(pages, [vec!["1", "2"], vec!["3", "4"], vec!["5"]]);
+
+    // Ids never minted have neither a history nor a burn record
+    let res = token_history(&suite, 6);
+    assert_eq!((res.history, res.burned), (None, None));
+}
+
+/// A collection whose marketing role is held by Alice
+fn marketing_suite() -> Suite {
+    let mut msg = instantiate_msg(1000, false);
+    msg.marketing = Some(InstantiateMarketingInfo {
+        project: Some("https://cw404.io".to_string()),
+        description: Some("A hybrid token".to_string()),
+        marketing: Some(ALICE.to_string()),
+        logo: Some(Logo::Url("https://cw404.io/logo.png".to_string())),
+    });
+    Suite::with_msg(msg)
+}
+
+fn update_marketing(project: &str, description: Option<&str>, marketing: &str) -> ExecuteMsg {
+    ExecuteMsg::UpdateMarketing {
+        project: Some(project.to_string()),
+        description: description.map(str::to_string),
+        marketing: Some(marketing.to_string()),
+    }
+}
+
+#[test]
+fn marketing_takes_the_marketing_role() {
+    let mut suite = marketing_suite();
+    let logo = || ExecuteMsg::UploadLogo(Logo::Url("https://cw404.io/new.png".to_string()));
+
+    // Not even the owner can update the marketing info without the role
+    for sender in [CREATOR, BOBBY] {
+        let err = suite
+            .execute(sender, update_marketing("", None, sender))
+            .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let err = suite.execute(sender, logo()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+    }
+
+    // Handing the role over leaves Alice without it
+    let msg = update_marketing("https://cw404.io/v2", None, BOBBY);
+    suite.execute(ALICE, msg).unwrap();
+    let err = suite.execute(ALICE, logo()).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    suite.execute(BOBBY, logo()).unwrap();
+
+    let info: MarketingInfoResponse = suite.query(QueryMsg::MarketingInfo {});
+    let expected = MarketingInfoResponse {
+        project: Some("https://cw404.io/v2".to_string()),
+        description: Some("A hybrid token".to_string()),
+        marketing: Some(Addr::unchecked(BOBBY)),
+        logo: Some(LogoInfo::Url("https://cw404.io/new.png".to_string())),
+    };
+    assert_eq!(info, expected);
+
+    // Without marketing info nobody holds the role
+    let mut suite = Suite::new(1000);
+    let err = suite
+        .execute(CREATOR, update_marketing("", None, CREATOR))
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+}
+
+#[test]
+fn empty_marketing_fields_are_cleared() {
+    let mut suite = marketing_suite();
+
+    // An unset field is kept, an empty one is cleared
+    suite
+        .execute(ALICE, update_marketing(" ", None, ALICE))
+        .unwrap();
+    let info: MarketingInfoResponse = suite.query(QueryMsg::MarketingInfo {});
+    assert_eq!(info.project, None);
+    assert_eq!(info.description, Some("A hybrid token".to_string()));
+    assert_eq!(info.marketing, Some(Addr::unchecked(ALICE)));
+
+    // Clearing the marketing address removes the role for good
+    suite
+        .execute(ALICE, update_marketing("", Some(""), ""))
+        .unwrap();
+    let info: MarketingInfoResponse = suite.query(QueryMsg::MarketingInfo {});
+    let expected = MarketingInfoResponse {
+        project: None,
+        description: None,
+        marketing: None,
+        logo: Some(LogoInfo::Url("https://cw404.io/logo.png".to_string())),
+    };
+    assert_eq!(info, expected);
+    let err = suite
+        .execute(ALICE, update_marketing("https://cw404.io", None, ALICE))
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+}
+
+#[test]
+fn embedded_logos_are_checked() {
+    let mut suite = marketing_suite();
+    let mut upload = |logo| suite.execute(ALICE, ExecuteMsg::UploadLogo(Logo::Embedded(logo)));
+
+    let png_header = [
+        0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 0x00, 0x00, 0x00, 0x0D, b'I', b'H', b'D',
+        b'R',
+    ];
+    let png = |len: usize| {
+        let mut data = png_header.to_vec();
+        data.resize(len, 0);
+        EmbeddedLogo::Png(Binary::from(data))
+    };
+    let svg = |data: &[u8]| EmbeddedLogo::Svg(Binary::from(data));
+    let large_svg = {
+        let mut data = b"<svg>".to_vec();
+        data.resize(LOGO_SIZE_CAP + 1, b' ');
+        data
+    };
+
+    let err = upload(svg(b"<html></html>")).unwrap_err();
+    assert_eq!(err, ContractError::InvalidXmlPreamble {});
+    let err = upload(svg(b"<?xml version=\"1.0\"><svg></svg>")).unwrap_err();
+    assert_eq!(err, ContractError::InvalidXmlPreamble {});
+    let err = upload(svg(&large_svg)).unwrap_err();
+    assert_eq!(err, ContractError::LogoTooBig {});
+    let err = upload(EmbeddedLogo::Png(Binary::from(&png_header[..8]))).unwrap_err();
+    assert_eq!(err, ContractError::InvalidPngHeader {});
+    let err = upload(png(LOGO_SIZE_CAP + 1)).unwrap_err();
+    assert_eq!(err, ContractError::LogoTooBig {});
+
+    upload(svg(b"<svg></svg>")).unwrap();
+    let svg_data = b"<?xml version=\"1.0\"?><svg></svg>";
+    upload(svg(svg_data)).unwrap();
+    let logo: DownloadLogoResponse = suite.query(QueryMsg::DownloadLogo {});
+    assert_eq!(logo.mime_type, "image/svg+xml");
+    assert_eq!(logo.data, Binary::from(svg_data));
+
+    // The PNG may take up the whole cap
+    let mut upload = |logo| suite.execute(ALICE, ExecuteMsg::UploadLogo(Logo::Embedded(logo)));
+    upload(png(LOGO_SIZE_CAP)).unwrap();
+    let logo: DownloadLogoResponse = suite.query(QueryMsg::DownloadLogo {});
+    assert_eq!(logo.mime_type, "image/png");
+    assert_eq!(logo.data.len(), LOGO_SIZE_CAP);
+    let info: MarketingInfoResponse = suite.query(QueryMsg::MarketingInfo {});
+    assert_eq!(info.logo, Some(LogoInfo::Embedded));
+
+    // Instantiating checks the logo as well
+    let mut msg = instantiate_msg(1000, false);
+    msg.marketing = Some(InstantiateMarketingInfo {
+        project: None,
+        description: None,
+        marketing: None,
+        logo: Some(Logo::Embedded(svg(b"<html></html>"))),
+    });
+    let mut app = App::default();
+    let code_id = app.store_code(cw404_contract());
+    let err = app
+        .instantiate_contract(code_id, Addr::unchecked(CREATOR), &msg, &[], "cw404", None)
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::InvalidXmlPreamble {}
+    );
+}
+
+/// A collection of 100 units per NFT burning in `order`, where Alice received NFTs 4, 5, 1
+/// and 2 in that order
+fn burn_order_suite(order: BurnOrder) -> Suite {
+    let mut msg = instantiate_msg(1000, false);
+    msg.decimals = 2;
+    msg.burn_order = Some(order);
+    let mut suite = Suite::with_msg(msg);
+    suite.transfer(CREATOR, BOBBY, 300).unwrap();
+    suite.transfer(CREATOR, ALICE, 200).unwrap();
+    for token_id in [1, 2] {
+        let msg = ExecuteMsg::TransferNft {
+            recipient: ALICE.to_string(),
+            token_id: Uint128::new(token_id),
+        };
+        suite.execute(BOBBY, msg).unwrap();
+    }
+    suite
+}
+
+#[test]
+fn fractional_transfers_burn_in_the_collection_order() {
+    let cases = [
+        (BurnOrder::Lifo, [2, 1, 5, 4]),
+        (BurnOrder::Fifo, [4, 5, 1, 2]),
+        (BurnOrder::LowestId, [1, 2, 4, 5]),
+        (BurnOrder::HighestId, [5, 4, 2, 1]),
+    ];
+    for (order, expected) in cases {
+        let mut suite = burn_order_suite(order.clone());
+        let expected = expected.map(Uint128::new);
+        assert_eq!(suite.next_to_burn(ALICE), expected, "{order:?}");
+        assert_eq!(suite.burn_alice_nfts(), expected, "{order:?}");
+        assert_eq!(suite.balance(ALICE), 50, "{order:?}");
+    }
+}
+
+#[test]
+fn set_burn_order_keeps_the_listed_nfts() {
+    let mut suite = burn_order_suite(BurnOrder::Lifo);
+    let set_burn_order = |ids: &[u128]| ExecuteMsg::SetBurnOrder {
+        token_ids: ids.iter().copied().map(Uint128::new).collect(),
+    };
+
+    // Only the holder may list their NFTs
+    let err = suite.execute(BOBBY, set_burn_order(&[4])).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // The listed NFTs burn last, in the order given
+    suite.execute(ALICE, set_burn_order(&[2, 4])).unwrap();
+    let expected = [1, 5, 2, 4].map(Uint128::new);
+    assert_eq!(suite.next_to_burn(ALICE), expected);
+
+    // A new list replaces the previous one, an empty list removes it
+    suite.execute(ALICE, set_burn_order(&[5])).unwrap();
+    let expected = [2, 1, 4, 5].map(Uint128::new);
+    assert_eq!(suite.next_to_burn(ALICE), expected);
+    suite.execute(ALICE, set_burn_order(&[])).unwrap();
+    let expected = [2, 1, 5, 4].map(Uint128::new);
+    assert_eq!(suite.next_to_burn(ALICE), expected);
+
+    suite.execute(ALICE, set_burn_order(&[2, 4])).unwrap();
+    let expected = [1, 5, 2, 4].map(Uint128::new);
+    assert_eq!(suite.burn_alice_nfts(), expected);
+}
@@ -1,5 +1,6 @@
 use cosmwasm_std::StdError;
 use cw_ownable::OwnershipError;
+use cw_utils::PaymentError;
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
@@ -13,30 +14,63 @@ pub enum ContractError {
     #[error(transparent)]
     Version(#[from] cw2::VersionError),
 
+    #[error(transparent)]
+    Payment(#[from] PaymentError),
+
     #[error("token_id already claimed")]
     Claimed {},
-   
+
     #[error("Unauthorized")]
     Unauthorized {},
-    
+
     #[error("InvalidSender")]
     InvalidSender {},
-    
+
     #[error("InvalidRecipient")]
     InvalidRecipient {},
-    
+
     #[error("AlreadyExists")]
     AlreadyExists {},
 
+    #[error("Minting cannot exceed the cap")]
+    CannotExceedCap {},
+
+    #[error("No token ids left to mint")]
+    TokenIdsExhausted {},
+
+    #[error("Rarity tier weights must add up to {total}")]
+    InvalidRarityTiers { total: u64 },
+
     #[error("PreventBurn")]
     PreventBurn {},
 
+    #[error("Every NFT left to {address} is locked")]
+    AllTokensLocked { address: String },
+
+    #[error("Cannot set to own account")]
+    CannotSetOwnAccount {},
+
     #[error("Cannot set approval that is already expired")]
     Expired {},
 
     #[error("Approval not found for: {spender}")]
     ApprovalNotFound { spender: String },
 
+    #[error("Reroll requires a fee of {fee}")]
+    InvalidRerollFee { fee: String },
+
     #[error("No withdraw address set")]
     NoWithdrawAddress {},
+
+    #[error("Logo binary data exceeds 5KB limit")]
+    LogoTooBig {},
+
+    #[error("Invalid xml preamble for SVG")]
+    InvalidXmlPreamble {},
+
+    #[error("Invalid png header")]
+    InvalidPngHeader {},
+
+    #[error("NFTs are still being migrated, send MigrateTokens to continue")]
+    MigrationPending {},
 }
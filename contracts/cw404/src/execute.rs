@@ -1,13 +1,26 @@
 use cosmwasm_std::{
-    to_json_binary, Binary, DepsMut, Env, MessageInfo, Response, StdResult, Storage, Uint128,
-    WasmMsg,
+    to_json_binary, Addr, BankMsg, Binary, BlockInfo, Coin, DepsMut, Env, Event, MessageInfo,
+    Order, Response, StdError, StdResult, Storage, Uint128, WasmMsg,
 };
+use cw20::{EmbeddedLogo, Logo, LogoInfo, MarketingInfoResponse};
+use cw_storage_plus::Item;
+use cw_utils::Expiration;
+use sha2::{Digest, Sha256};
 
+use crate::contract::{CONTRACT_NAME, CONTRACT_VERSION};
 use crate::error::ContractError;
+use crate::migrate::migrate_token_batch;
 use crate::msg::{ExecuteMsg, InstantiateMsg};
 use crate::state::{
-    Cw20ReceiveMsg, ALLOWANCE, APPROVED_FOR_ALL, BALANCES, BASE_TOKEN_URI, DECIMALS, GET_APPROVED,
-    LOCKED, MINTED, NAME, OWNED, OWNED_INDEX, OWNER, OWNER_OF, SYMBOL, TOTAL_SUPPLY, WHITELIST,
+    token_owner, tokens, BurnRecord, Cw20ReceiveMsg, Metadata, OwnerRecord, RarityTier, RerollFee,
+    Token, TokenHistory, ACQUIRED_COUNT, ALLOWANCE, ALLOWANCE_EXPIRES, ALLOWANCE_OWNERS,
+    APPROVED_FOR_ALL, APPROVED_FOR_ALL_EXPIRES, BALANCES, BASE_TOKEN_URI, BURNED, BURN_ORDER,
+    DECIMALS, EXEMPT_BALANCE, GET_APPROVED, GET_APPROVED_EXPIRES, HISTORY_LIMIT, LEGACY_NFT_EVENTS,
+    LOCKED, LOCKED_UNTIL, LOGO, LOGO_SIZE_CAP, MARKETING_INFO, MAX_TOKEN_ID, METADATA,
+    MIGRATING_TOKENS, MINTED, MINTER, NAME, NFTS_BURNED, NFT_HOLDERS, NFT_SUPPLY, OWNED_COUNT,
+    OWNER_LOCKS, RARITY_TIERS, RARITY_TOTAL_WEIGHT, RECONCILE_LIMIT, RECONCILING, RECYCLED_IDS,
+    RECYCLE_IDS, REROLL_FEE, SEED, SUPPLY_CAP, SYMBOL, TOKEN_HISTORY, TOTAL_SUPPLY, WHITELIST,
+    WHITELIST_COUNT,
 };
 
 pub fn instantiate(
@@ -16,16 +29,64 @@ pub fn instantiate(
     info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
-    let total_supply = msg.total_native_supply.u128() * ((10u128).pow(msg.decimals.into()));
+    cw2::set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    let unit = Uint128::from(10u128).pow(msg.decimals.into());
+    let supply_cap = msg
+        .total_native_supply
+        .checked_mul(unit)
+        .map_err(StdError::from)?;
+    let total_supply = msg
+        .initial_native_supply
+        .unwrap_or(msg.total_native_supply)
+        .checked_mul(unit)
+        .map_err(StdError::from)?;
+    if total_supply > supply_cap {
+        return Err(ContractError::CannotExceedCap {});
+    }
     DECIMALS.save(deps.storage, &msg.decimals)?;
-    TOTAL_SUPPLY.save(deps.storage, &Uint128::from(total_supply))?;
+    TOTAL_SUPPLY.save(deps.storage, &total_supply)?;
+    SUPPLY_CAP.save(deps.storage, &supply_cap)?;
     MINTED.save(deps.storage, &Uint128::zero())?;
+    RECYCLE_IDS.save(deps.storage, &msg.recycle_ids.unwrap_or(false))?;
+    LEGACY_NFT_EVENTS.save(deps.storage, &false)?;
+    BURN_ORDER.save(deps.storage, &msg.burn_order.unwrap_or_default())?;
+    WHITELIST_COUNT.save(deps.storage, &0)?;
+    NFT_SUPPLY.save(deps.storage, &0)?;
+    EXEMPT_BALANCE.save(deps.storage, &Uint128::zero())?;
+    NFTS_BURNED.save(deps.storage, &0)?;
+    NFT_HOLDERS.save(deps.storage, &0)?;
+    MAX_TOKEN_ID.save(deps.storage, &msg.total_native_supply)?;
     NAME.save(deps.storage, &msg.name)?;
     SYMBOL.save(deps.storage, &msg.symbol)?;
 
-    OWNER.save(deps.storage, &info.sender.to_string())?;
+    cw_ownable::initialize_owner(deps.storage, deps.api, Some(info.sender.as_str()))?;
+    if let Some(minter) = msg.minter {
+        MINTER.save(deps.storage, &deps.api.addr_validate(&minter)?)?;
+    }
 
-    BALANCES.save(deps.storage, &info.sender, &Uint128::from(total_supply))?;
+    BALANCES.save(deps.storage, &info.sender, &total_supply)?;
+
+    if let Some(marketing) = msg.marketing {
+        let logo = match marketing.logo {
+            Some(logo) => {
+                verify_logo(&logo)?;
+                LOGO.save(deps.storage, &logo)?;
+                Some(logo_info(logo))
+            }
+            None => None,
+        };
+        let marketing_info = MarketingInfoResponse {
+            project: marketing.project,
+            description: marketing.description,
+            marketing: marketing
+                .marketing
+                .map(|addr| deps.api.addr_validate(&addr))
+                .transpose()?,
+            logo,
+        };
+        MARKETING_INFO.save(deps.storage, &marketing_info)?;
+    }
 
     Ok(Response::new()
         .add_attribute("action", "mint")
@@ -39,28 +100,52 @@ pub fn execute(
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
+    // NFTs still kept in the owned lists of an upgrade are moved before anything else runs
+    if MIGRATING_TOKENS.exists(deps.storage) && !matches!(msg, ExecuteMsg::MigrateTokens {}) {
+        return Err(ContractError::MigrationPending {});
+    }
+
     match msg {
         ExecuteMsg::Approve {
             spender,
             token_id,
-            expires: _,
-        } => approve(deps, env, info, spender, token_id),
-        ExecuteMsg::ApproveAll {
-            operator,
-            expires: _,
-        } => approve_all(deps, env, info, operator),
+            expires,
+        } => approve(deps, env, info, spender, token_id, expires),
+        ExecuteMsg::ApproveAll { operator, expires } => {
+            approve_all(deps, env, info, operator, expires)
+        }
         ExecuteMsg::IncreaseAllowance {
             spender,
             amount,
-            expires: _expires,
-        } => approve(deps, env, info, spender, amount),
+            expires,
+        } => increase_allowance(deps, env, info, spender, amount, expires),
+        ExecuteMsg::DecreaseAllowance {
+            spender,
+            amount,
+            expires,
+        } => decrease_allowance(deps, env, info, spender, amount, expires),
+        ExecuteMsg::Revoke { spender, token_id } => revoke(deps, env, info, spender, token_id),
         ExecuteMsg::RevokeAll { operator } => revoke_all(deps, env, info, operator),
-        // This is the default implementation in erc404
+        // Added to ensure compatibility with cw20
+        ExecuteMsg::Burn { amount } => {
+            burn(deps, env, info.clone(), info.sender.to_string(), amount)
+        }
+        // Added to ensure compatibility with cw20
+        ExecuteMsg::BurnFrom { owner, amount } => burn_from(deps, env, info, owner, amount),
+        // Added to ensure compatibility with cw721
+        ExecuteMsg::BurnNft { token_id } => burn_nft(deps, env, info, token_id),
+        // Added to ensure compatibility with cw20
         ExecuteMsg::TransferFrom {
             owner,
             recipient,
             amount,
-        } => transfer_from(deps, env, info, owner, recipient, amount, None),
+        } => transfer_from(deps, env, info, owner, recipient, amount),
+        // Delegated counterpart of TransferNft
+        ExecuteMsg::TransferNftFrom {
+            owner,
+            recipient,
+            token_id,
+        } => transfer_nft_from(deps, env, info, owner, recipient, token_id, None),
         // This is the default implementation in erc404
         ExecuteMsg::Transfer { recipient, amount } => transfer(
             deps,
@@ -74,7 +159,7 @@ pub fn execute(
         ExecuteMsg::TransferNft {
             recipient,
             token_id,
-        } => transfer_from(
+        } => transfer_nft_from(
             deps,
             env,
             info.clone(),
@@ -111,8 +196,24 @@ pub fn execute(
             msg,
             token_id,
         ),
+        ExecuteMsg::BatchTransferNft {
+            recipient,
+            token_ids,
+        } => batch_transfer_nft(deps, env, info, recipient, token_ids),
+        ExecuteMsg::BatchSendNft {
+            contract,
+            token_ids,
+            msg,
+        } => batch_send_nft(deps, env, info, contract, token_ids, msg),
+        ExecuteMsg::MultiTransfer { transfers } => multi_transfer(deps, env, info, transfers),
+        ExecuteMsg::SetBurnOrder { token_ids } => set_burn_order(deps, env, info, token_ids),
         // Additional feature added by dojo team to prevent accidental burning of CW721 tokens that a user may wish to keep (as cw20 transfers might burn tokens)
         ExecuteMsg::SetLock { token_id, state } => set_lock(deps, env, info, token_id, state),
+        ExecuteMsg::SetLocks {
+            token_ids,
+            state,
+            until,
+        } => set_locks(deps, env, info, token_ids, state, until),
 
         // Event functions
         ExecuteMsg::GenerateNftEvent {
@@ -131,378 +232,1207 @@ pub fn execute(
 
         // Auxillary functions
         ExecuteMsg::SetWhitelist { target, state } => set_whitelist(deps, env, info, target, state),
+        ExecuteMsg::SetSelfNftExempt { state } => set_self_nft_exempt(deps, env, info, state),
+        ExecuteMsg::ReconcileNfts { address } => reconcile_nfts(deps, env, info, address),
+        ExecuteMsg::MigrateTokens {} => migrate_tokens(deps, env, info),
         ExecuteMsg::SetBaseTokenUri { uri } => set_base_token_uri(deps, env, info, uri),
+        ExecuteMsg::SetMetadata { token_id, metadata } => {
+            set_metadata(deps, env, info, token_id, metadata)
+        }
+        ExecuteMsg::Mint { recipient, amount } => mint(deps, env, info, recipient, amount),
+        ExecuteMsg::UpdateMinter { new_minter } => update_minter(deps, env, info, new_minter),
+        ExecuteMsg::Reroll { token_id } => reroll(deps, env, info, token_id),
+        ExecuteMsg::SetRerollFee { fee, recipient } => {
+            set_reroll_fee(deps, env, info, fee, recipient)
+        }
+        ExecuteMsg::SetRarityTiers { tiers } => set_rarity_tiers(deps, env, info, tiers),
+        ExecuteMsg::UpdateOwnership(action) => update_ownership(deps, env, info, action),
+        ExecuteMsg::UpdateMarketing {
+            project,
+            description,
+            marketing,
+        } => update_marketing(deps, env, info, project, description, marketing),
+        ExecuteMsg::UploadLogo(logo) => upload_logo(deps, env, info, logo),
     }
 }
 
 pub fn set_whitelist(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     target: String,
     state: bool,
 ) -> Result<Response, ContractError> {
-    let owner = OWNER.load(deps.storage)?;
-    if info.sender.to_string() != owner {
-        return Err(ContractError::Unauthorized {});
-    }
-
-    // Prevents minting new NFTs by simply toggling the whitelist status.
-    // This ensures that the capability to mint new tokens cannot be exploited
-    // by reopen whitelist state.
-    if state {
-        let owned_list = OWNED
-            .may_load(deps.storage, target.to_string())?
-            .unwrap_or(vec![]);
-
-        for _ in 0..owned_list.len() {
-            _burn(deps.storage, env.clone(), target.to_string())?;
-        }
-    }
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
 
-    WHITELIST.save(deps.storage, target.to_string(), &state)?;
-    Ok(Response::new()
+    let (events, remaining) = _set_nft_exempt(deps.branch(), &env, target.clone(), state)?;
+    Ok(add_nft_events(deps.storage, &env, Response::new(), events)?
+        .add_attribute("remaining", remaining.to_string())
         .add_attribute("action", "set_whitelist")
         .add_attribute("address", target.to_string())
         .add_attribute("state", state.to_string()))
 }
 
-pub fn set_lock(
-    deps: DepsMut,
-    _env: Env,
+pub fn set_self_nft_exempt(
+    mut deps: DepsMut,
+    env: Env,
     info: MessageInfo,
-    target: Uint128,
     state: bool,
 ) -> Result<Response, ContractError> {
-    let owner_of = OWNER_OF
-        .may_load(deps.storage, target.to_string())?
-        .unwrap_or("".to_string());
-    if info.sender.to_string() != owner_of {
-        return Err(ContractError::Unauthorized {});
-    }
+    let address = info.sender.to_string();
+    let (events, remaining) = _set_nft_exempt(deps.branch(), &env, address.clone(), state)?;
+    Ok(add_nft_events(deps.storage, &env, Response::new(), events)?
+        .add_attribute("action", "set_self_nft_exempt")
+        .add_attribute("address", address)
+        .add_attribute("state", state.to_string())
+        .add_attribute("remaining", remaining.to_string()))
+}
 
-    LOCKED.save(deps.storage, target.to_string(), &state)?;
-    Ok(Response::new()
-        .add_attribute("action", "set_lock")
-        .add_attribute("target", target.to_string())
-        .add_attribute("state", state.to_string()))
+pub fn reconcile_nfts(
+    deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    let address_addr = deps.api.addr_validate(&address)?;
+
+    // Only addresses leaving the whitelist have NFTs to catch up on
+    let reconciling = RECONCILING
+        .may_load(deps.storage, address.clone())?
+        .unwrap_or_default();
+    let (events, remaining) = if reconciling {
+        _reconcile(deps.storage, &env, &address_addr)?
+    } else {
+        (vec![], 0)
+    };
+
+    Ok(add_nft_events(deps.storage, &env, Response::new(), events)?
+        .add_attribute("action", "reconcile_nfts")
+        .add_attribute("address", address)
+        .add_attribute("remaining", remaining.to_string()))
 }
 
-pub fn set_base_token_uri(
+pub fn migrate_tokens(
     deps: DepsMut,
     _env: Env,
-    info: MessageInfo,
-    uri: String,
+    _info: MessageInfo,
 ) -> Result<Response, ContractError> {
-    let owner = OWNER.load(deps.storage)?;
-    if info.sender.to_string() != owner {
-        return Err(ContractError::Unauthorized {});
-    }
+    let (migrated, done) = migrate_token_batch(deps.storage)?;
 
-    BASE_TOKEN_URI.save(deps.storage, &uri.to_string())?;
-    Ok(Response::new().add_attribute("action", "set_token_uri"))
+    Ok(Response::new()
+        .add_attribute("action", "migrate_tokens")
+        .add_attribute("migrated", migrated.to_string())
+        .add_attribute("done", done.to_string()))
 }
 
-fn transfer_from(
+pub fn mint(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    from: String,
-    to: String,
-    amount_or_id: Uint128,
-    event: Option<String>,
+    recipient: String,
+    amount: Uint128,
 ) -> Result<Response, ContractError> {
-    let from_addr = deps.api.addr_validate(&from)?;
-    let to_addr = deps.api.addr_validate(&to)?;
+    let minter = MINTER.may_load(deps.storage)?;
+    if minter.as_ref() != Some(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
 
-    let owner_of = OWNER_OF
-        .may_load(deps.storage, amount_or_id.to_string())?
-        .unwrap_or("".to_string());
-    let minted = MINTED.load(deps.storage)?;
-    let is_approved_for_all = APPROVED_FOR_ALL
-        .may_load(deps.storage, (from.to_string(), info.sender.to_string()))?
-        .unwrap_or(false);
+    let total_supply = TOTAL_SUPPLY
+        .load(deps.storage)?
+        .checked_add(amount)
+        .map_err(StdError::from)?;
+    let supply_cap = SUPPLY_CAP.may_load(deps.storage)?.unwrap_or_default();
+    if total_supply > supply_cap {
+        return Err(ContractError::CannotExceedCap {});
+    }
+    TOTAL_SUPPLY.save(deps.storage, &total_supply)?;
 
-    let get_approved = GET_APPROVED
-        .may_load(deps.storage, amount_or_id.to_string())?
-        .unwrap_or("".to_string());
+    let recipient_addr = deps.api.addr_validate(&recipient)?;
     let unit = get_unit(deps.storage)?;
+    let balance_before = BALANCES
+        .may_load(deps.storage, &recipient_addr)?
+        .unwrap_or_default();
+    let balance_after = _update_balance(deps.storage, &recipient_addr, amount, true)?;
 
-    if amount_or_id <= minted {
-        if from != owner_of {
-            return Err(ContractError::InvalidSender {});
+    let mut events = vec![];
+    // Skip minting for certain addresses to save gas
+    let whitelisted = WHITELIST
+        .may_load(deps.storage, recipient.clone())?
+        .unwrap_or_default();
+    if !whitelisted {
+        let tokens_to_mint = (balance_after / unit) - (balance_before / unit);
+        for _i in 0..tokens_to_mint.u128() {
+            events.push(_mint(deps.storage, &env, recipient.clone())?);
         }
+    }
 
-        if to == "" {
-            return Err(ContractError::InvalidRecipient {});
-        }
+    Ok(add_nft_events(deps.storage, &env, Response::new(), events)?
+        .add_attribute("action", "mint")
+        .add_attribute("to", recipient)
+        .add_attribute("amount", amount))
+}
 
-        if info.sender.to_string() != from
-            && !is_approved_for_all
-            && info.sender.to_string() != get_approved
-        {
-            return Err(ContractError::Unauthorized {});
-        }
+pub fn update_minter(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    new_minter: Option<String>,
+) -> Result<Response, ContractError> {
+    let minter = MINTER.may_load(deps.storage)?;
+    if minter.as_ref() != Some(&info.sender) {
+        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+    }
 
-        // Prevents exploiting two different states of transferFrom can lead to a bug that allows minting
-        // CW-721 tokens out of thin air through a whitelist
-        if WHITELIST
-            .may_load(deps.storage, to.clone())?
-            .unwrap_or_default()
-        {
-            return Err(ContractError::InvalidRecipient {});
+    match &new_minter {
+        Some(new_minter) => {
+            MINTER.save(deps.storage, &deps.api.addr_validate(new_minter)?)?;
         }
+        None => MINTER.remove(deps.storage),
+    }
 
-        BALANCES.update(
-            deps.storage,
-            &from_addr,
-            |balance: Option<Uint128>| -> StdResult<_> {
-                Ok(balance.unwrap_or_default().checked_sub(unit)?)
-            },
-        )?;
-        BALANCES.update(
-            deps.storage,
-            &to_addr,
-            |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + unit) },
-        )?;
+    Ok(Response::new()
+        .add_attribute("action", "update_minter")
+        .add_attribute("new_minter", new_minter.unwrap_or("None".to_string())))
+}
 
-        OWNER_OF.save(deps.storage, amount_or_id.to_string(), &to)?;
+fn update_marketing(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    project: Option<String>,
+    description: Option<String>,
+    marketing: Option<String>,
+) -> Result<Response, ContractError> {
+    let mut marketing_info = MARKETING_INFO.may_load(deps.storage)?.unwrap_or_default();
+    if marketing_info.marketing.as_ref() != Some(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
 
-        GET_APPROVED.remove(deps.storage, amount_or_id.to_string());
-        let mut vec_updated_id = OWNED
-            .may_load(deps.storage, from.clone())?
-            .unwrap_or(vec![]);
+    match project {
+        Some(empty) if empty.trim().is_empty() => marketing_info.project = None,
+        Some(project) => marketing_info.project = Some(project),
+        None => (),
+    }
+    match description {
+        Some(empty) if empty.trim().is_empty() => marketing_info.description = None,
+        Some(description) => marketing_info.description = Some(description),
+        None => (),
+    }
+    match marketing {
+        Some(empty) if empty.trim().is_empty() => marketing_info.marketing = None,
+        Some(marketing) => marketing_info.marketing = Some(deps.api.addr_validate(&marketing)?),
+        None => (),
+    }
 
-        let updated_id = vec_updated_id.get(vec_updated_id.len() - 1).unwrap();
-        let owned_index = OWNED_INDEX
-            .may_load(deps.storage, amount_or_id.to_string())?
-            .unwrap_or(Uint128::zero());
+    // Nothing left to show once every field is cleared and there is no logo
+    if marketing_info.project.is_none()
+        && marketing_info.description.is_none()
+        && marketing_info.marketing.is_none()
+        && marketing_info.logo.is_none()
+    {
+        MARKETING_INFO.remove(deps.storage);
+    } else {
+        MARKETING_INFO.save(deps.storage, &marketing_info)?;
+    }
 
-        OWNED_INDEX.save(deps.storage, updated_id.to_string(), &owned_index)?;
+    Ok(Response::new()
+        .add_attribute("action", "update_marketing")
+        .add_attribute("sender", info.sender))
+}
 
-        vec_updated_id[owned_index.u128() as usize] = updated_id.clone();
-        vec_updated_id.pop();
+fn upload_logo(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    logo: Logo,
+) -> Result<Response, ContractError> {
+    let mut marketing_info = MARKETING_INFO.may_load(deps.storage)?.unwrap_or_default();
+    if marketing_info.marketing.as_ref() != Some(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
 
-        OWNED.save(deps.storage, from.clone(), &vec_updated_id)?;
+    verify_logo(&logo)?;
+    LOGO.save(deps.storage, &logo)?;
+    marketing_info.logo = Some(logo_info(logo));
+    MARKETING_INFO.save(deps.storage, &marketing_info)?;
 
-        let mut to_owned = OWNED.may_load(deps.storage, to.clone())?.unwrap_or(vec![]);
-        to_owned.push(amount_or_id);
-        OWNED.save(deps.storage, to.clone(), &to_owned)?;
+    Ok(Response::new()
+        .add_attribute("action", "upload_logo")
+        .add_attribute("sender", info.sender))
+}
 
-        OWNED_INDEX.save(
-            deps.storage,
-            amount_or_id.to_string(),
-            &Uint128::from((to_owned.len() - 1) as u128),
-        )?;
-        Ok(Response::new()
-            .add_message(WasmMsg::Execute {
-                contract_addr: env.contract.address.to_string(),
-                msg: to_json_binary(&ExecuteMsg::GenerateNftEvent {
-                    sender: from.clone(),
-                    recipient: to.clone(),
-                    token_id: amount_or_id,
-                })?,
-                funds: vec![],
-            })
-            .add_attribute("action", event.unwrap_or("transfer".to_string()))
-            .add_attribute("from", from)
-            .add_attribute("to", to)
-            .add_attribute("amount", unit.to_string()))
-    } else {
-        let allowed = ALLOWANCE
-            .may_load(deps.storage, (from.clone(), info.sender.to_string()))?
-            .unwrap_or(Uint128::zero());
-        if allowed != Uint128::MAX {
-            ALLOWANCE.update(
-                deps.storage,
-                (from.clone(), info.sender.to_string()),
-                |allow: Option<Uint128>| -> StdResult<_> {
-                    Ok(allow.unwrap_or_default().checked_sub(amount_or_id)?)
-                },
-            )?;
-        }
+/// What `MarketingInfo` reports of a logo, embedded data is only served by `DownloadLogo`
+fn logo_info(logo: Logo) -> LogoInfo {
+    match logo {
+        Logo::Url(url) => LogoInfo::Url(url),
+        Logo::Embedded(_) => LogoInfo::Embedded,
+    }
+}
 
-        let response = _transfer(
-            deps,
-            env,
-            info.clone(),
-            from,
-            to,
-            amount_or_id,
-            event.unwrap_or("transfer_from".to_string()),
-        )
-        .unwrap();
-        Ok(response.add_attribute("by", info.sender))
+/// Checks an embedded logo is a PNG or an SVG within `LOGO_SIZE_CAP`
+fn verify_logo(logo: &Logo) -> Result<(), ContractError> {
+    match logo {
+        Logo::Embedded(EmbeddedLogo::Svg(logo)) => verify_xml_logo(logo),
+        Logo::Embedded(EmbeddedLogo::Png(logo)) => verify_png_logo(logo),
+        Logo::Url(_) => Ok(()),
     }
 }
 
-fn approve(
+/// Checks the SVG starts with an xml preamble or an svg tag. It does not validate the
+/// whole document, which would be too costly on chain
+fn verify_xml_logo(logo: &[u8]) -> Result<(), ContractError> {
+    if logo.len() > LOGO_SIZE_CAP {
+        return Err(ContractError::LogoTooBig {});
+    }
+
+    let preamble = logo
+        .split_inclusive(|c| *c == b'>')
+        .next()
+        .ok_or(ContractError::InvalidXmlPreamble {})?;
+    const PREFIX: &[u8] = b"<?xml ";
+    const POSTFIX: &[u8] = b"?>";
+    let has_preamble = preamble.starts_with(PREFIX) && preamble.ends_with(POSTFIX);
+    if !has_preamble && !logo.starts_with(b"<svg") {
+        return Err(ContractError::InvalidXmlPreamble {});
+    }
+    Ok(())
+}
+
+/// Checks the PNG starts with the PNG signature and an IHDR chunk
+fn verify_png_logo(logo: &[u8]) -> Result<(), ContractError> {
+    if logo.len() > LOGO_SIZE_CAP {
+        return Err(ContractError::LogoTooBig {});
+    }
+
+    // 8 bytes of signature, then the length and type of the IHDR chunk
+    const HEADER: [u8; 16] = [
+        0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 0x00, 0x00, 0x00, 0x0D, b'I', b'H', b'D',
+        b'R',
+    ];
+    if !logo.starts_with(&HEADER) {
+        return Err(ContractError::InvalidPngHeader {});
+    }
+    Ok(())
+}
+
+pub fn reroll(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
-    spender: String,
-    amount_or_id: Uint128,
+    token_id: Uint128,
 ) -> Result<Response, ContractError> {
-    let minted = MINTED.load(deps.storage)?;
+    let owner = token_owner(deps.storage, token_id)?;
+    if info.sender != owner {
+        return Err(ContractError::Unauthorized {});
+    }
 
-    if amount_or_id <= minted && amount_or_id > Uint128::zero() {
-        let owner = OWNER_OF
-            .may_load(deps.storage, amount_or_id.to_string())?
-            .unwrap_or("".to_string());
+    // Prevents rerolling if user has locked their token
+    if is_token_locked(deps.storage, &env.block, token_id)? {
+        return Err(ContractError::PreventBurn {});
+    }
 
-        let is_approved_for_all = APPROVED_FOR_ALL
-            .may_load(deps.storage, (owner.to_string(), info.sender.to_string()))?
-            .unwrap_or(false);
-        if info.sender.to_string() != owner.to_string() && !is_approved_for_all {
-            return Err(ContractError::Unauthorized {});
+    let mut res = Response::new();
+    match REROLL_FEE.may_load(deps.storage)? {
+        Some(RerollFee { fee, recipient }) => {
+            let paid = cw_utils::must_pay(&info, &fee.denom)?;
+            if paid != fee.amount {
+                return Err(ContractError::InvalidRerollFee {
+                    fee: fee.to_string(),
+                });
+            }
+            res = res.add_message(BankMsg::Send {
+                to_address: recipient.to_string(),
+                amount: vec![fee],
+            });
         }
+        None => cw_utils::nonpayable(&info)?,
+    }
 
-        GET_APPROVED.save(deps.storage, amount_or_id.to_string(), &spender)?;
-        Ok(Response::new()
-            .add_attribute("action", "approve")
-            .add_attribute("sender", owner.to_string())
-            .add_attribute("spender", spender)
-            .add_attribute("token_id", amount_or_id))
-    } else {
-        // ALLOWANCE
-        ALLOWANCE.save(
-            deps.storage,
-            (info.sender.to_string(), spender.clone()),
-            &amount_or_id,
-        )?;
+    // Take the next id before releasing the old one, so a reroll never hands back the same
+    // id and seed. When every other id is held there is nothing to reroll into
+    let new_id = _next_id(deps.storage)?;
+    _destroy(deps.storage, &env.block, token_id)?;
+    let mint_event = _mint_id(deps.storage, &env, owner.clone(), new_id)?;
 
-        Ok(Response::new()
-            .add_attribute("action", "approve")
-            .add_attribute("sender", info.sender)
-            .add_attribute("spender", spender)
-            .add_attribute("token_id", amount_or_id))
-    }
+    let events = vec![
+        NftEvent::Burn {
+            owner: owner.clone(),
+            token_id,
+        },
+        mint_event,
+    ];
+    Ok(add_nft_events(deps.storage, &env, res, events)?
+        .add_attribute("action", "reroll")
+        .add_attribute("owner", owner)
+        .add_attribute("burned_token_id", token_id)
+        .add_attribute("minted_token_id", new_id))
 }
 
-fn approve_all(
+pub fn set_reroll_fee(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
-    operator: String,
+    fee: Option<Coin>,
+    recipient: Option<String>,
 ) -> Result<Response, ContractError> {
-    deps.api.addr_validate(&operator)?;
-
-    APPROVED_FOR_ALL.save(
-        deps.storage,
-        (info.sender.to_string(), operator.clone()),
-        &true,
-    )?;
-
-    Ok(Response::new()
-        .add_attribute("action", "approve_all")
-        .add_attribute("sender", info.sender)
-        .add_attribute("operator", operator))
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+    let res = Response::new().add_attribute("action", "set_reroll_fee");
+    // A zero fee could never be paid, it leaves rerolls free instead
+    match fee {
+        Some(fee) if !fee.amount.is_zero() => {
+            let recipient = recipient.ok_or(ContractError::NoWithdrawAddress {})?;
+            let recipient = deps.api.addr_validate(&recipient)?;
+            let res = res
+                .add_attribute("fee", fee.to_string())
+                .add_attribute("recipient", recipient.to_string());
+            REROLL_FEE.save(deps.storage, &RerollFee { fee, recipient })?;
+            Ok(res)
+        }
+        _ => {
+            REROLL_FEE.remove(deps.storage);
+            Ok(res)
+        }
+    }
 }
 
-fn revoke_all(
+pub fn set_rarity_tiers(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
-    operator: String,
+    tiers: Vec<RarityTier>,
 ) -> Result<Response, ContractError> {
-    deps.api.addr_validate(&operator)?;
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
 
-    APPROVED_FOR_ALL.save(
-        deps.storage,
-        (info.sender.to_string(), operator.clone()),
-        &false,
-    )?;
+    if tiers.is_empty() {
+        RARITY_TIERS.remove(deps.storage);
+    } else {
+        let total = tiers
+            .iter()
+            .try_fold(0u64, |total, tier| total.checked_add(tier.weight));
+        if total != Some(RARITY_TOTAL_WEIGHT) {
+            return Err(ContractError::InvalidRarityTiers {
+                total: RARITY_TOTAL_WEIGHT,
+            });
+        }
+        RARITY_TIERS.save(deps.storage, &tiers)?;
+    }
 
     Ok(Response::new()
-        .add_attribute("action", "revoke_all")
-        .add_attribute("sender", info.sender)
-        .add_attribute("operator", operator.to_string()))
+        .add_attribute("action", "set_rarity_tiers")
+        .add_attribute("tiers", tiers.len().to_string()))
 }
 
-fn transfer(
+pub fn update_ownership(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    from: String,
-    to: String,
-    amount: Uint128,
+    action: cw_ownable::Action,
 ) -> Result<Response, ContractError> {
-    _transfer(deps, env, info, from, to, amount, "transfer".to_string())
+    let ownership = cw_ownable::update_ownership(deps, &env.block, &info.sender, action)?;
+    Ok(Response::new().add_attributes(ownership.into_attributes()))
 }
 
-fn send(
+pub fn set_lock(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    from: String,
-    contract: String,
-    msg: Binary,
-    amount: Uint128,
+    target: Uint128,
+    state: bool,
 ) -> Result<Response, ContractError> {
-    let response = _transfer(
-        deps,
-        env,
-        info.clone(),
-        from,
-        contract.clone(),
-        amount,
-        "send".to_string(),
-    )
-    .unwrap();
-    Ok(response.add_message(
-        Cw20ReceiveMsg {
-            sender: info.sender.into(),
-            amount,
-            msg,
-        }
-        .into_cosmos_msg(contract)?,
-    ))
+    _set_lock(deps.storage, &env.block, &info.sender, target, state, None)?;
+    Ok(Response::new()
+        .add_attribute("action", "set_lock")
+        .add_attribute("target", target.to_string())
+        .add_attribute("state", state.to_string()))
 }
 
-fn send_nft(
+pub fn set_locks(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    from: String,
-    contract: String,
-    msg: Binary,
-    amount: Uint128,
+    token_ids: Vec<Uint128>,
+    state: bool,
+    until: Option<Expiration>,
 ) -> Result<Response, ContractError> {
-    let response = transfer_from(
-        deps,
-        env,
-        info.clone(),
-        from,
-        contract.clone(),
-        amount,
-        Some("send".to_string()),
-    )
-    .unwrap();
+    // Reject locks that are already expired
+    if until.map_or(false, |until| until.is_expired(&env.block)) {
+        return Err(ContractError::Expired {});
+    }
 
-    Ok(response.add_message(
-        cw721::Cw721ReceiveMsg {
-            sender: info.sender.into(),
-            token_id: amount.to_string(),
-            msg,
-        }
-        .into_cosmos_msg(contract)?,
-    ))
-}
+    for token_id in &token_ids {
+        _set_lock(
+            deps.storage,
+            &env.block,
+            &info.sender,
+            *token_id,
+            state,
+            until,
+        )?;
+    }
 
-fn get_unit(storage: &dyn Storage) -> Result<Uint128, ContractError> {
-    let decimals = DECIMALS.load(storage)?;
-    Ok(Uint128::from(10u128).pow(decimals.into()))
+    let token_ids: Vec<String> = token_ids.iter().map(|id| id.to_string()).collect();
+    Ok(Response::new()
+        .add_attribute("action", "set_locks")
+        .add_attribute("token_ids", token_ids.join(","))
+        .add_attribute("state", state.to_string())
+        .add_attribute(
+            "until",
+            until.map_or("never".to_string(), |u| u.to_string()),
+        ))
 }
 
-fn _transfer(
+pub fn set_base_token_uri(
     deps: DepsMut,
-    env: Env,
+    _env: Env,
+    info: MessageInfo,
+    uri: String,
+) -> Result<Response, ContractError> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+    BASE_TOKEN_URI.save(deps.storage, &uri.to_string())?;
+    Ok(Response::new().add_attribute("action", "set_token_uri"))
+}
+
+pub fn set_metadata(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    token_id: Uint128,
+    metadata: Option<Metadata>,
+) -> Result<Response, ContractError> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+    match &metadata {
+        Some(metadata) => METADATA.save(deps.storage, token_id.to_string(), metadata)?,
+        None => METADATA.remove(deps.storage, token_id.to_string()),
+    }
+    Ok(Response::new()
+        .add_attribute("action", "set_metadata")
+        .add_attribute("token_id", token_id))
+}
+
+fn transfer_nft_from(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    from: String,
+    to: String,
+    token_id: Uint128,
+    event: Option<String>,
+) -> Result<Response, ContractError> {
+    let unit = get_unit(deps.storage)?;
+    let nft_event = _transfer_nft(
+        deps.branch(),
+        &env,
+        &info,
+        from.clone(),
+        to.clone(),
+        token_id,
+    )?;
+
+    let events = vec![nft_event];
+    Ok(add_nft_events(deps.storage, &env, Response::new(), events)?
+        .add_attribute("action", event.unwrap_or("transfer".to_string()))
+        .add_attribute("from", from)
+        .add_attribute("to", to)
+        .add_attribute("amount", unit.to_string()))
+}
+
+fn set_burn_order(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    token_ids: Vec<Uint128>,
+) -> Result<Response, ContractError> {
+    let owner = info.sender.to_string();
+
+    // Only the previously listed ids carry a rank, so clearing them does not touch the others
+    let ranked = tokens()
+        .idx
+        .burn_queue
+        .sub_prefix((owner.clone(), 1))
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (id, token) in ranked {
+        let token = Token {
+            burn_rank: None,
+            ..token
+        };
+        tokens().save(deps.storage, id, &token)?;
+    }
+
+    for (rank, token_id) in token_ids.iter().enumerate() {
+        let token = tokens()
+            .may_load(deps.storage, token_id.u128())?
+            .filter(|t| t.owner == owner)
+            .ok_or(ContractError::Unauthorized {})?;
+        let token = Token {
+            burn_rank: Some(rank as u64),
+            ..token
+        };
+        tokens().save(deps.storage, token_id.u128(), &token)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "set_burn_order")
+        .add_attribute("owner", owner)
+        .add_attribute("tokens", token_ids.len().to_string()))
+}
+
+fn batch_transfer_nft(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipient: String,
+    token_ids: Vec<Uint128>,
+) -> Result<Response, ContractError> {
+    _batch_transfer_nft(
+        deps,
+        env,
+        info,
+        recipient,
+        token_ids,
+        "batch_transfer_nft".to_string(),
+    )
+}
+
+fn batch_send_nft(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    contract: String,
+    token_ids: Vec<Uint128>,
+    msg: Binary,
+) -> Result<Response, ContractError> {
+    let mut response = _batch_transfer_nft(
+        deps,
+        env,
+        info.clone(),
+        contract.clone(),
+        token_ids.clone(),
+        "batch_send_nft".to_string(),
+    )?;
+
+    for token_id in token_ids {
+        response = response.add_message(
+            cw721::Cw721ReceiveMsg {
+                sender: info.sender.to_string(),
+                token_id: token_id.to_string(),
+                msg: msg.clone(),
+            }
+            .into_cosmos_msg(contract.clone())?,
+        );
+    }
+    Ok(response)
+}
+
+fn multi_transfer(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    transfers: Vec<(String, Uint128)>,
+) -> Result<Response, ContractError> {
+    let from = info.sender.to_string();
+    let mut events = vec![];
+    let mut total = Uint128::zero();
+    for (recipient, amount) in &transfers {
+        events.extend(_transfer_balance(
+            deps.branch(),
+            &env,
+            from.clone(),
+            recipient.clone(),
+            *amount,
+        )?);
+        total = total.checked_add(*amount).map_err(StdError::from)?;
+    }
+
+    Ok(add_nft_events(deps.storage, &env, Response::new(), events)?
+        .add_attribute("action", "multi_transfer")
+        .add_attribute("from", from)
+        .add_attribute("recipients", transfers.len().to_string())
+        .add_attribute("amount", total))
+}
+
+/// Moves every token in `token_ids` from `info.sender` to `to` with a single aggregated response
+fn _batch_transfer_nft(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    to: String,
+    token_ids: Vec<Uint128>,
+    event: String,
+) -> Result<Response, ContractError> {
+    let from = info.sender.to_string();
+    let mut events = vec![];
+    for token_id in &token_ids {
+        events.push(_transfer_nft(
+            deps.branch(),
+            &env,
+            &info,
+            from.clone(),
+            to.clone(),
+            *token_id,
+        )?);
+    }
+
+    let unit = get_unit(deps.storage)?;
+    let amount = unit
+        .checked_mul(Uint128::from(token_ids.len() as u128))
+        .map_err(StdError::from)?;
+    let token_ids: Vec<String> = token_ids.iter().map(|id| id.to_string()).collect();
+    Ok(add_nft_events(deps.storage, &env, Response::new(), events)?
+        .add_attribute("action", event)
+        .add_attribute("from", from)
+        .add_attribute("to", to)
+        .add_attribute("token_ids", token_ids.join(","))
+        .add_attribute("amount", amount))
+}
+
+/// Moves `token_id` and the unit backing it from `from` to `to`, on behalf of `info.sender`
+fn _transfer_nft(
+    deps: DepsMut,
+    env: &Env,
+    info: &MessageInfo,
+    from: String,
+    to: String,
+    token_id: Uint128,
+) -> Result<NftEvent, ContractError> {
+    let from_addr = deps.api.addr_validate(&from)?;
+    let to_addr = deps.api.addr_validate(&to)?;
+
+    let owner_of = token_owner(deps.storage, token_id)?;
+    let is_approved_for_all = is_approved_for_all(
+        deps.storage,
+        &env.block,
+        from.to_string(),
+        info.sender.to_string(),
+    )?;
+
+    let get_approved = GET_APPROVED
+        .may_load(deps.storage, token_id.to_string())?
+        .unwrap_or_default();
+    let get_approved_expires = GET_APPROVED_EXPIRES
+        .may_load(deps.storage, token_id.to_string())?
+        .unwrap_or_default();
+    let unit = get_unit(deps.storage)?;
+
+    if from != owner_of {
+        return Err(ContractError::InvalidSender {});
+    }
+
+    if to.is_empty() {
+        return Err(ContractError::InvalidRecipient {});
+    }
+
+    if info.sender != from
+        && !is_approved_for_all
+        && (info.sender != get_approved || get_approved_expires.is_expired(&env.block))
+    {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // Prevents exploiting two different states of transferFrom can lead to a bug that allows minting
+    // CW-721 tokens out of thin air through a whitelist
+    if WHITELIST
+        .may_load(deps.storage, to.clone())?
+        .unwrap_or_default()
+    {
+        return Err(ContractError::InvalidRecipient {});
+    }
+
+    _update_balance(deps.storage, &from_addr, unit, false)?;
+    _update_balance(deps.storage, &to_addr, unit, true)?;
+
+    GET_APPROVED.remove(deps.storage, token_id.to_string());
+    GET_APPROVED_EXPIRES.remove(deps.storage, token_id.to_string());
+    // A lock belongs to the holder who set it, the recipient starts unlocked
+    LOCKED.remove(deps.storage, token_id.to_string());
+    LOCKED_UNTIL.remove(deps.storage, token_id.to_string());
+    OWNER_LOCKS.remove(deps.storage, (from.clone(), token_id.u128()));
+    _acquire(deps.storage, token_id, to.clone())?;
+    _record_owner(deps.storage, &env.block, token_id, to.clone())?;
+    Ok(NftEvent::Transfer {
+        sender: from,
+        recipient: to,
+        token_id,
+    })
+}
+
+fn transfer_from(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    from: String,
+    to: String,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let allowed = ALLOWANCE
+        .may_load(deps.storage, (from.clone(), info.sender.to_string()))?
+        .unwrap_or(Uint128::zero());
+    let allowance_expires = ALLOWANCE_EXPIRES
+        .may_load(deps.storage, (from.clone(), info.sender.to_string()))?
+        .unwrap_or_default();
+    if allowance_expires.is_expired(&env.block) {
+        return Err(ContractError::Expired {});
+    }
+    if allowed != Uint128::MAX {
+        ALLOWANCE.update(
+            deps.storage,
+            (from.clone(), info.sender.to_string()),
+            |allow: Option<Uint128>| -> StdResult<_> {
+                Ok(allow.unwrap_or_default().checked_sub(amount)?)
+            },
+        )?;
+    }
+
+    let response = _transfer(
+        deps,
+        env,
+        info.clone(),
+        from,
+        to,
+        amount,
+        "transfer_from".to_string(),
+    )?;
+    Ok(response.add_attribute("by", info.sender))
+}
+
+fn approve(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    spender: String,
+    token_id: Uint128,
+    expires: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    // Reject approvals that are already expired
+    let expires = expires.unwrap_or_default();
+    if expires.is_expired(&env.block) {
+        return Err(ContractError::Expired {});
+    }
+
+    let owner = token_owner(deps.storage, token_id)?;
+
+    let is_approved_for_all = is_approved_for_all(
+        deps.storage,
+        &env.block,
+        owner.to_string(),
+        info.sender.to_string(),
+    )?;
+    if info.sender != owner && !is_approved_for_all {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    GET_APPROVED.save(deps.storage, token_id.to_string(), &spender)?;
+    GET_APPROVED_EXPIRES.save(deps.storage, token_id.to_string(), &expires)?;
+    Ok(Response::new()
+        .add_attribute("action", "approve")
+        .add_attribute("sender", owner)
+        .add_attribute("spender", spender)
+        .add_attribute("token_id", token_id))
+}
+
+fn revoke(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    spender: String,
+    token_id: Uint128,
+) -> Result<Response, ContractError> {
+    let owner = token_owner(deps.storage, token_id)?;
+
+    let is_approved_for_all = is_approved_for_all(
+        deps.storage,
+        &env.block,
+        owner.to_string(),
+        info.sender.to_string(),
+    )?;
+    if info.sender != owner && !is_approved_for_all {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let approved = GET_APPROVED
+        .may_load(deps.storage, token_id.to_string())?
+        .unwrap_or_default();
+    if approved != spender {
+        return Err(ContractError::ApprovalNotFound { spender });
+    }
+
+    GET_APPROVED.remove(deps.storage, token_id.to_string());
+    GET_APPROVED_EXPIRES.remove(deps.storage, token_id.to_string());
+    Ok(Response::new()
+        .add_attribute("action", "revoke")
+        .add_attribute("sender", info.sender)
+        .add_attribute("spender", spender)
+        .add_attribute("token_id", token_id))
+}
+
+fn increase_allowance(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    spender: String,
+    amount: Uint128,
+    expires: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    let spender_addr = deps.api.addr_validate(&spender)?;
+    if spender_addr == info.sender {
+        return Err(ContractError::CannotSetOwnAccount {});
+    }
+
+    let key = (info.sender.to_string(), spender.clone());
+    if let Some(exp) = expires {
+        if exp.is_expired(&env.block) {
+            return Err(ContractError::Expired {});
+        }
+        ALLOWANCE_EXPIRES.save(deps.storage, key.clone(), &exp)?;
+    }
+    ALLOWANCE.update(
+        deps.storage,
+        key,
+        |allow: Option<Uint128>| -> StdResult<_> {
+            Ok(allow.unwrap_or_default().checked_add(amount)?)
+        },
+    )?;
+    ALLOWANCE_OWNERS.save(
+        deps.storage,
+        (spender.clone(), info.sender.to_string()),
+        &true,
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "increase_allowance")
+        .add_attribute("owner", info.sender)
+        .add_attribute("spender", spender)
+        .add_attribute("amount", amount))
+}
+
+fn decrease_allowance(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    spender: String,
+    amount: Uint128,
+    expires: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    let spender_addr = deps.api.addr_validate(&spender)?;
+    if spender_addr == info.sender {
+        return Err(ContractError::CannotSetOwnAccount {});
+    }
+
+    let key = (info.sender.to_string(), spender.clone());
+    let allowance = ALLOWANCE
+        .may_load(deps.storage, key.clone())?
+        .unwrap_or_default()
+        .checked_sub(amount)
+        .unwrap_or_default();
+
+    // Drop the allowance entirely once it reaches zero
+    if allowance.is_zero() {
+        ALLOWANCE.remove(deps.storage, key.clone());
+        ALLOWANCE_EXPIRES.remove(deps.storage, key);
+        ALLOWANCE_OWNERS.remove(deps.storage, (spender.clone(), info.sender.to_string()));
+    } else {
+        if let Some(exp) = expires {
+            if exp.is_expired(&env.block) {
+                return Err(ContractError::Expired {});
+            }
+            ALLOWANCE_EXPIRES.save(deps.storage, key.clone(), &exp)?;
+        }
+        ALLOWANCE.save(deps.storage, key, &allowance)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "decrease_allowance")
+        .add_attribute("owner", info.sender)
+        .add_attribute("spender", spender)
+        .add_attribute("amount", amount))
+}
+
+fn approve_all(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    operator: String,
+    expires: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    deps.api.addr_validate(&operator)?;
+
+    // Reject approvals that are already expired
+    let expires = expires.unwrap_or_default();
+    if expires.is_expired(&env.block) {
+        return Err(ContractError::Expired {});
+    }
+
+    APPROVED_FOR_ALL.save(
+        deps.storage,
+        (info.sender.to_string(), operator.clone()),
+        &true,
+    )?;
+    APPROVED_FOR_ALL_EXPIRES.save(
+        deps.storage,
+        (info.sender.to_string(), operator.clone()),
+        &expires,
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "approve_all")
+        .add_attribute("sender", info.sender)
+        .add_attribute("operator", operator))
+}
+
+fn revoke_all(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    operator: String,
+) -> Result<Response, ContractError> {
+    deps.api.addr_validate(&operator)?;
+
+    APPROVED_FOR_ALL.save(
+        deps.storage,
+        (info.sender.to_string(), operator.clone()),
+        &false,
+    )?;
+    APPROVED_FOR_ALL_EXPIRES.remove(deps.storage, (info.sender.to_string(), operator.clone()));
+
+    Ok(Response::new()
+        .add_attribute("action", "revoke_all")
+        .add_attribute("sender", info.sender)
+        .add_attribute("operator", operator.to_string()))
+}
+
+fn transfer(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    from: String,
+    to: String,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    _transfer(deps, env, info, from, to, amount, "transfer".to_string())
+}
+
+fn send(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    from: String,
+    contract: String,
+    msg: Binary,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let response = _transfer(
+        deps,
+        env,
+        info.clone(),
+        from,
+        contract.clone(),
+        amount,
+        "send".to_string(),
+    )?;
+    Ok(response.add_message(
+        Cw20ReceiveMsg {
+            sender: info.sender.into(),
+            amount,
+            msg,
+        }
+        .into_cosmos_msg(contract)?,
+    ))
+}
+
+fn send_nft(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    from: String,
+    contract: String,
+    msg: Binary,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let response = transfer_nft_from(
+        deps,
+        env,
+        info.clone(),
+        from,
+        contract.clone(),
+        amount,
+        Some("send".to_string()),
+    )?;
+
+    Ok(response.add_message(
+        cw721::Cw721ReceiveMsg {
+            sender: info.sender.into(),
+            token_id: amount.to_string(),
+            msg,
+        }
+        .into_cosmos_msg(contract)?,
+    ))
+}
+
+fn burn(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    from: String,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let from_addr = deps.api.addr_validate(&from)?;
+    let balance_before = BALANCES
+        .may_load(deps.storage, &from_addr)?
+        .unwrap_or_default();
+
+    let balance_after = _update_balance(deps.storage, &from_addr, amount, false)?;
+    TOTAL_SUPPLY.update(deps.storage, |supply| -> StdResult<_> {
+        Ok(supply.checked_sub(amount)?)
+    })?;
+
+    let events = _burn_released(deps.storage, &env, &from, balance_before, balance_after)?;
+
+    let res = add_nft_events(deps.storage, &env, Response::new(), events)?;
+    let res = if info.sender == from {
+        res.add_attribute("action", "burn")
+    } else {
+        res.add_attribute("action", "burn_from")
+            .add_attribute("by", info.sender)
+    };
+    Ok(res
+        .add_attribute("from", from)
+        .add_attribute("amount", amount))
+}
+
+fn burn_from(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    owner: String,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let key = (owner.clone(), info.sender.to_string());
+    let allowance_expires = ALLOWANCE_EXPIRES
+        .may_load(deps.storage, key.clone())?
+        .unwrap_or_default();
+    if allowance_expires.is_expired(&env.block) {
+        return Err(ContractError::Expired {});
+    }
+    let allowed = ALLOWANCE
+        .may_load(deps.storage, key.clone())?
+        .unwrap_or_default();
+    if allowed != Uint128::MAX {
+        ALLOWANCE.save(
+            deps.storage,
+            key,
+            &allowed.checked_sub(amount).map_err(StdError::from)?,
+        )?;
+    }
+
+    burn(deps, env, info, owner, amount)
+}
+
+fn burn_nft(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token_id: Uint128,
+) -> Result<Response, ContractError> {
+    let owner = token_owner(deps.storage, token_id)?;
+    if owner.is_empty() {
+        return Err(ContractError::InvalidSender {});
+    }
+
+    let is_approved_for_all = is_approved_for_all(
+        deps.storage,
+        &env.block,
+        owner.to_string(),
+        info.sender.to_string(),
+    )?;
+    let get_approved = GET_APPROVED
+        .may_load(deps.storage, token_id.to_string())?
+        .unwrap_or_default();
+    let get_approved_expires = GET_APPROVED_EXPIRES
+        .may_load(deps.storage, token_id.to_string())?
+        .unwrap_or_default();
+    if info.sender != owner
+        && !is_approved_for_all
+        && (info.sender != get_approved || get_approved_expires.is_expired(&env.block))
+    {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // Prevents burning if user has locked their token
+    if is_token_locked(deps.storage, &env.block, token_id)? {
+        return Err(ContractError::PreventBurn {});
+    }
+
+    // The NFT is backed by exactly one unit of the owner's balance
+    let unit = get_unit(deps.storage)?;
+    let owner_addr = deps.api.addr_validate(&owner)?;
+    _update_balance(deps.storage, &owner_addr, unit, false)?;
+    TOTAL_SUPPLY.update(deps.storage, |supply| -> StdResult<_> {
+        Ok(supply.checked_sub(unit)?)
+    })?;
+
+    _destroy(deps.storage, &env.block, token_id)?;
+
+    let events = vec![NftEvent::Burn {
+        owner: owner.clone(),
+        token_id,
+    }];
+    Ok(add_nft_events(deps.storage, &env, Response::new(), events)?
+        .add_attribute("action", "burn_nft")
+        .add_attribute("sender", info.sender)
+        .add_attribute("owner", owner)
+        .add_attribute("token_id", token_id)
+        .add_attribute("amount", unit))
+}
+
+fn is_approved_for_all(
+    storage: &dyn Storage,
+    block: &BlockInfo,
+    owner: String,
+    operator: String,
+) -> StdResult<bool> {
+    let approved = APPROVED_FOR_ALL
+        .may_load(storage, (owner.clone(), operator.clone()))?
+        .unwrap_or(false);
+    let expires = APPROVED_FOR_ALL_EXPIRES
+        .may_load(storage, (owner, operator))?
+        .unwrap_or_default();
+    Ok(approved && !expires.is_expired(block))
+}
+
+fn get_unit(storage: &dyn Storage) -> Result<Uint128, ContractError> {
+    let decimals = DECIMALS.load(storage)?;
+    Ok(Uint128::from(10u128).pow(decimals.into()))
+}
+
+fn _transfer(
+    mut deps: DepsMut,
+    env: Env,
     _info: MessageInfo,
     from: String,
     to: String,
     amount: Uint128,
     event: String,
 ) -> Result<Response, ContractError> {
+    let events = _transfer_balance(deps.branch(), &env, from.clone(), to.clone(), amount)?;
+
+    Ok(add_nft_events(deps.storage, &env, Response::new(), events)?
+        .add_attribute("action", event)
+        .add_attribute("from", from)
+        .add_attribute("to", to)
+        .add_attribute("amount", amount))
+}
+
+/// Moves `amount` from `from` to `to`, burning and minting NFTs as whole units change hands
+fn _transfer_balance(
+    deps: DepsMut,
+    env: &Env,
+    from: String,
+    to: String,
+    amount: Uint128,
+) -> Result<Vec<NftEvent>, ContractError> {
     let from_addr = deps.api.addr_validate(&from)?;
     let to_addr = deps.api.addr_validate(&to)?;
     let unit = get_unit(deps.storage)?;
@@ -513,40 +1443,25 @@ fn _transfer(
         .may_load(deps.storage, &to_addr)?
         .unwrap_or_default();
 
-    BALANCES.update(
-        deps.storage,
-        &from_addr,
-        |balance: Option<Uint128>| -> StdResult<_> {
-            Ok(balance.unwrap_or_default().checked_sub(amount)?)
-        },
-    )?;
-
-    BALANCES.update(
-        deps.storage,
-        &to_addr,
-        |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + amount) },
-    )?;
+    _update_balance(deps.storage, &from_addr, amount, false)?;
+    _update_balance(deps.storage, &to_addr, amount, true)?;
 
-    let whitelist_from = WHITELIST
-        .may_load(deps.storage, from.clone())?
+    // Read once both sides are updated, a transfer to self leaves the balance unchanged
+    let balance_after_sender = BALANCES
+        .may_load(deps.storage, &from_addr)?
         .unwrap_or_default();
+
     let whitelist_to = WHITELIST
         .may_load(deps.storage, to.clone())?
         .unwrap_or_default();
 
-    let mut messages = vec![];
-    // Skip burn for certain addresses to save gas
-    if !whitelist_from {
-        let tokens_to_burn = (balance_before_sender / unit)
-            - (BALANCES
-                .may_load(deps.storage, &from_addr)?
-                .unwrap_or_default()
-                / unit);
-        for _i in 0..tokens_to_burn.u128() {
-            let msg = _burn(deps.storage, env.clone(), from.clone())?;
-            messages.push(msg);
-        }
-    }
+    let mut events = _burn_released(
+        deps.storage,
+        env,
+        &from,
+        balance_before_sender,
+        balance_after_sender,
+    )?;
 
     // Skip minting for certain addresses to save gas
     if !whitelist_to {
@@ -556,91 +1471,528 @@ fn _transfer(
             / unit)
             - (balance_before_receiver / unit);
         for _i in 0..tokens_to_mint.u128() {
-            let msg = _mint(deps.storage, env.clone(), to.clone())?;
-            messages.push(msg);
+            events.push(_mint(deps.storage, env, to.clone())?);
         }
     }
 
-    Ok(Response::new()
-        .add_messages(messages)
-        .add_attribute("action", event.to_string())
-        .add_attribute("from", from)
-        .add_attribute("to", to)
-        .add_attribute("amount", amount))
+    Ok(events)
 }
 
-fn _mint(storage: &mut dyn Storage, env: Env, to: String) -> Result<WasmMsg, ContractError> {
-    if to == "" {
-        return Err(ContractError::InvalidRecipient {});
+/// Burns the NFTs `address` no longer has a whole token for once its balance dropped from
+/// `before` to `after`. Whitelisted addresses hold no NFTs and skip this to save gas, except
+/// while reconciling, when they burn down to their new balance like any holder
+fn _burn_released(
+    storage: &mut dyn Storage,
+    env: &Env,
+    address: &str,
+    before: Uint128,
+    after: Uint128,
+) -> Result<Vec<NftEvent>, ContractError> {
+    let unit = get_unit(storage)?;
+    if RECONCILING.has(storage, address.to_string()) {
+        let owned = owned_count(storage, address)?;
+        let count = owned.saturating_sub((after / unit).u128());
+        return _burn(storage, env, address.to_string(), count);
+    }
+    if WHITELIST
+        .may_load(storage, address.to_string())?
+        .unwrap_or_default()
+    {
+        return Ok(vec![]);
+    }
+    let count = (before / unit) - (after / unit);
+    _burn(storage, env, address.to_string(), count.u128())
+}
+
+/// Marks `target` as exempt from NFTs or not. An exempt address holds no NFTs, so all of its
+/// NFTs are burnt when it becomes exempt. When it stops being exempt, one NFT is minted per
+/// whole token of its balance, see `_reconcile`. Returns the number of NFTs still to mint
+fn _set_nft_exempt(
+    deps: DepsMut,
+    env: &Env,
+    target: String,
+    state: bool,
+) -> Result<(Vec<NftEvent>, u128), ContractError> {
+    let target_addr = deps.api.addr_validate(&target)?;
+
+    // Prevents minting new NFTs by simply toggling the whitelist status.
+    // This ensures that the capability to mint new tokens cannot be exploited
+    // by reopen whitelist state.
+    if state {
+        let owned = owned_count(deps.storage, &target)?;
+        let events = _burn(deps.storage, env, target.clone(), owned)?;
+        RECONCILING.remove(deps.storage, target.clone());
+        _set_whitelisted(deps.storage, target, true)?;
+        return Ok((events, 0));
+    }
+
+    // The address stays whitelisted while its NFTs are minted, so transfers in between
+    // cannot mint or burn on top of a partial set
+    _set_whitelisted(deps.storage, target.clone(), true)?;
+    RECONCILING.save(deps.storage, target, &true)?;
+    _reconcile(deps.storage, env, &target_addr)
+}
+
+/// Adds `address` to or removes it from `WHITELIST`, keeping `WHITELIST_COUNT` in sync
+fn _set_whitelisted(storage: &mut dyn Storage, address: String, state: bool) -> StdResult<()> {
+    let whitelisted = WHITELIST
+        .may_load(storage, address.clone())?
+        .unwrap_or_default();
+    if whitelisted == state {
+        return Ok(());
+    }
+
+    // The balance of the address moves in or out of the exempt balance with it
+    let balance = BALANCES
+        .may_load(storage, &Addr::unchecked(&address))?
+        .unwrap_or_default();
+    let exempt = EXEMPT_BALANCE.may_load(storage)?.unwrap_or_default();
+    let count = WHITELIST_COUNT.may_load(storage)?.unwrap_or_default();
+    if state {
+        WHITELIST.save(storage, address, &true)?;
+        EXEMPT_BALANCE.save(storage, &(exempt + balance))?;
+        WHITELIST_COUNT.save(storage, &(count + 1))
+    } else {
+        WHITELIST.remove(storage, address);
+        EXEMPT_BALANCE.save(storage, &exempt.saturating_sub(balance))?;
+        WHITELIST_COUNT.save(storage, &count.saturating_sub(1))
+    }
+}
+
+/// Adds `amount` to the balance of `address` or takes it away, counting it in
+/// `EXEMPT_BALANCE` when the address is whitelisted. Returns the new balance
+fn _update_balance(
+    storage: &mut dyn Storage,
+    address: &Addr,
+    amount: Uint128,
+    add: bool,
+) -> StdResult<Uint128> {
+    let balance = BALANCES.may_load(storage, address)?.unwrap_or_default();
+    let balance = if add {
+        balance.checked_add(amount)?
+    } else {
+        balance.checked_sub(amount)?
+    };
+    BALANCES.save(storage, address, &balance)?;
+
+    if WHITELIST
+        .may_load(storage, address.to_string())?
+        .unwrap_or_default()
+    {
+        let exempt = EXEMPT_BALANCE.may_load(storage)?.unwrap_or_default();
+        let exempt = if add {
+            exempt + amount
+        } else {
+            exempt.saturating_sub(amount)
+        };
+        EXEMPT_BALANCE.save(storage, &exempt)?;
+    }
+    Ok(balance)
+}
+
+/// Brings the NFTs of `address` up to one per whole token of its balance, by at most
+/// `RECONCILE_LIMIT` NFTs, and takes it off the whitelist once they match.
+/// Returns the number of NFTs still missing
+fn _reconcile(
+    storage: &mut dyn Storage,
+    env: &Env,
+    address: &Addr,
+) -> Result<(Vec<NftEvent>, u128), ContractError> {
+    let owned = owned_count(storage, address.as_str())?;
+    let balance = BALANCES.may_load(storage, address)?.unwrap_or_default();
+    let target = (balance / get_unit(storage)?).u128();
+
+    // Reconciling addresses burn on the way out, an excess is only left by tokens sent away
+    // while whitelisted before they did
+    let events = if owned > target {
+        let count = (owned - target).min(RECONCILE_LIMIT);
+        _burn(storage, env, address.to_string(), count)?
+    } else {
+        let count = (target - owned).min(RECONCILE_LIMIT);
+        (0..count)
+            .map(|_| _mint(storage, env, address.to_string()))
+            .collect::<Result<Vec<_>, _>>()?
+    };
+
+    let remaining = target.abs_diff(owned) - events.len() as u128;
+    if remaining == 0 {
+        RECONCILING.remove(storage, address.to_string());
+        _set_whitelisted(storage, address.to_string(), false)?;
+    }
+    Ok((events, remaining))
+}
+
+/// Picks the id of the next NFT, preferring recycled ids when recycling is enabled
+fn _next_id(storage: &mut dyn Storage) -> Result<Uint128, ContractError> {
+    let recycle = RECYCLE_IDS.may_load(storage)?.unwrap_or(false);
+    if recycle {
+        if let Some(id) = RECYCLED_IDS.pop_front(storage)? {
+            return Ok(id);
+        }
     }
 
     let minted = MINTED.load(storage)?;
     let id = minted + Uint128::one();
+    if recycle && id > MAX_TOKEN_ID.load(storage)? {
+        return Err(ContractError::TokenIdsExhausted {});
+    }
     MINTED.save(storage, &id)?;
+    Ok(id)
+}
+
+/// Queues a burnt id for reuse when recycling is enabled, ids above `MAX_TOKEN_ID` are retired
+fn _recycle_id(storage: &mut dyn Storage, id: Uint128) -> StdResult<()> {
+    if RECYCLE_IDS.may_load(storage)?.unwrap_or(false) && id <= MAX_TOKEN_ID.load(storage)? {
+        RECYCLED_IDS.push_back(storage, &id)?;
+    }
+    Ok(())
+}
+
+/// Derives the seed of a freshly minted id from the block, the contract, the recipient and the id
+fn _seed(env: &Env, to: &str, id: Uint128) -> u64 {
+    let hash = Sha256::new()
+        .chain_update(env.block.height.to_be_bytes())
+        .chain_update(env.block.time.nanos().to_be_bytes())
+        .chain_update(env.contract.address.as_bytes())
+        .chain_update(to.as_bytes())
+        .chain_update(id.u128().to_be_bytes())
+        .finalize();
+    let mut seed = [0u8; 8];
+    seed.copy_from_slice(&hash[..8]);
+    u64::from_be_bytes(seed)
+}
+
+/// Takes a burnt id away from its owner, records the burn, clears its state and queues it
+/// for reuse
+fn _destroy(storage: &mut dyn Storage, block: &BlockInfo, id: Uint128) -> StdResult<()> {
+    if let Some(token) = tokens().may_load(storage, id.u128())? {
+        _add_owned(storage, &token.owner, false)?;
+        OWNER_LOCKS.remove(storage, (token.owner.clone(), id.u128()));
+        tokens().remove(storage, id.u128())?;
+        _count(storage, &NFT_SUPPLY, false)?;
+        _count(storage, &NFTS_BURNED, true)?;
+
+        let record = BurnRecord {
+            height: block.height,
+            time: block.time,
+            last_owner: token.owner,
+        };
+        BURNED.save(storage, id.u128(), &record)?;
+    }
+    GET_APPROVED.remove(storage, id.to_string());
+    GET_APPROVED_EXPIRES.remove(storage, id.to_string());
+    SEED.remove(storage, id.to_string());
+    LOCKED.remove(storage, id.to_string());
+    LOCKED_UNTIL.remove(storage, id.to_string());
+    _recycle_id(storage, id)
+}
+
+/// Gives `id` to `to` as its latest acquisition, placing it in the burn queue of `to`
+fn _acquire(storage: &mut dyn Storage, id: Uint128, to: String) -> StdResult<()> {
+    let seq = ACQUIRED_COUNT.may_load(storage)?.unwrap_or_default() + 1;
+    ACQUIRED_COUNT.save(storage, &seq)?;
+
+    if let Some(token) = tokens().may_load(storage, id.u128())? {
+        _add_owned(storage, &token.owner, false)?;
+    }
+    _add_owned(storage, &to, true)?;
+
+    let order = BURN_ORDER.may_load(storage)?.unwrap_or_default();
+    let token = Token {
+        owner: to,
+        burn_key: order.key(seq, id.u128()),
+        burn_rank: None,
+    };
+    tokens().save(storage, id.u128(), &token)
+}
 
-    let owner_of = OWNER_OF
+/// Appends `owner` to the history of `id`, dropping the oldest owner past `HISTORY_LIMIT`.
+/// Ids minted before histories were recorded have none
+fn _record_owner(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    id: Uint128,
+    owner: String,
+) -> StdResult<()> {
+    let mut history = match TOKEN_HISTORY.may_load(storage, id.u128())? {
+        Some(history) => history,
+        None => return Ok(()),
+    };
+    if history.owners.len() >= HISTORY_LIMIT {
+        history.owners.remove(0);
+    }
+    history.owners.push(OwnerRecord {
+        owner,
+        height: block.height,
+    });
+    TOKEN_HISTORY.save(storage, id.u128(), &history)
+}
+
+/// Counts one NFT more or less in `OWNED_COUNT` for `owner`, and `owner` in `NFT_HOLDERS`
+/// when it gets its first NFT or gives up its last
+fn _add_owned(storage: &mut dyn Storage, owner: &str, add: bool) -> StdResult<()> {
+    let count = owned_count(storage, owner)? as u64;
+    if add {
+        if count == 0 {
+            _count(storage, &NFT_HOLDERS, true)?;
+        }
+        OWNED_COUNT.save(storage, owner.to_string(), &(count + 1))
+    } else if count > 1 {
+        OWNED_COUNT.save(storage, owner.to_string(), &(count - 1))
+    } else {
+        OWNED_COUNT.remove(storage, owner.to_string());
+        _count(storage, &NFT_HOLDERS, false)
+    }
+}
+
+/// Adds one to or takes one from `counter`
+fn _count(storage: &mut dyn Storage, counter: &Item<u64>, add: bool) -> StdResult<()> {
+    let count = counter.may_load(storage)?.unwrap_or_default();
+    if add {
+        counter.save(storage, &(count + 1))
+    } else {
+        counter.save(storage, &count.saturating_sub(1))
+    }
+}
+
+/// Number of NFTs held by `owner`
+pub fn owned_count(storage: &dyn Storage, owner: &str) -> StdResult<u128> {
+    Ok(OWNED_COUNT
+        .may_load(storage, owner.to_string())?
+        .unwrap_or_default()
+        .into())
+}
+
+/// Locks or unlocks `token_id` on behalf of `sender`, which must own it or operate for its owner
+fn _set_lock(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    sender: &Addr,
+    token_id: Uint128,
+    state: bool,
+    until: Option<Expiration>,
+) -> Result<(), ContractError> {
+    let owner_of = token_owner(storage, token_id)?;
+    if *sender != owner_of
+        && !is_approved_for_all(storage, block, owner_of.clone(), sender.to_string())?
+    {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if state {
+        LOCKED.save(storage, token_id.to_string(), &true)?;
+        OWNER_LOCKS.save(storage, (owner_of, token_id.u128()), &true)?;
+    } else {
+        LOCKED.remove(storage, token_id.to_string());
+        OWNER_LOCKS.remove(storage, (owner_of, token_id.u128()));
+    }
+    match until {
+        Some(until) if state => LOCKED_UNTIL.save(storage, token_id.to_string(), &until)?,
+        _ => LOCKED_UNTIL.remove(storage, token_id.to_string()),
+    }
+    Ok(())
+}
+
+/// Whether `id` is locked against burns, a lock past its expiration no longer counts
+pub fn is_token_locked(storage: &dyn Storage, block: &BlockInfo, id: Uint128) -> StdResult<bool> {
+    let locked = LOCKED.may_load(storage, id.to_string())?.unwrap_or(false);
+    let until = LOCKED_UNTIL
         .may_load(storage, id.to_string())?
-        .unwrap_or("".to_string());
+        .unwrap_or_default();
+    Ok(locked && !until.is_expired(block))
+}
 
-    if owner_of != "" {
-        return Err(ContractError::AlreadyExists {});
+/// First `limit` unlocked token ids of `owner` in the order they are burnt: ids left out of
+/// the holder's `SetBurnOrder` list follow the collection burn order, then the listed ids follow
+pub fn burn_queue(
+    storage: &dyn Storage,
+    block: &BlockInfo,
+    owner: &str,
+    limit: usize,
+) -> StdResult<Vec<Uint128>> {
+    let index = tokens().idx.burn_queue;
+    let unlisted =
+        index
+            .sub_prefix((owner.to_string(), 0))
+            .keys(storage, None, None, Order::Ascending);
+    let listed =
+        index
+            .sub_prefix((owner.to_string(), 1))
+            .keys(storage, None, None, Order::Ascending);
+
+    let mut queue = vec![];
+    for id in unlisted.chain(listed) {
+        if queue.len() >= limit {
+            break;
+        }
+        let id = Uint128::from(id?);
+        if !is_token_locked(storage, block, id)? {
+            queue.push(id);
+        }
+    }
+    Ok(queue)
+}
+
+fn _mint(storage: &mut dyn Storage, env: &Env, to: String) -> Result<NftEvent, ContractError> {
+    if to.is_empty() {
+        return Err(ContractError::InvalidRecipient {});
     }
 
-    OWNER_OF.save(storage, id.to_string(), &to)?;
+    let id = _next_id(storage)?;
+    _mint_id(storage, env, to, id)
+}
 
-    let mut owned = OWNED.may_load(storage, to.clone())?.unwrap_or(vec![]);
-    owned.push(id);
-    OWNED.save(storage, to.clone(), &owned)?;
-    OWNED_INDEX.save(
-        storage,
-        id.to_string(),
-        &Uint128::from((owned.len() - 1) as u128),
-    )?;
+/// Mints `id` to `to`
+fn _mint_id(
+    storage: &mut dyn Storage,
+    env: &Env,
+    to: String,
+    id: Uint128,
+) -> Result<NftEvent, ContractError> {
+    if tokens().has(storage, id.u128()) {
+        return Err(ContractError::AlreadyExists {});
+    }
 
-    Ok(WasmMsg::Execute {
-        contract_addr: env.contract.address.to_string(),
-        msg: to_json_binary(&ExecuteMsg::GenerateNftMintEvent {
-            sender: env.contract.address.to_string(),
-            recipient: to,
-            token_id: id,
-        })?,
-        funds: vec![],
+    SEED.save(storage, id.to_string(), &_seed(env, &to, id))?;
+    _acquire(storage, id, to.clone())?;
+    _count(storage, &NFT_SUPPLY, true)?;
+
+    // A recycled id starts a new history
+    let history = TokenHistory {
+        minted_height: env.block.height,
+        minted_time: env.block.time,
+        first_owner: to.clone(),
+        owners: vec![OwnerRecord {
+            owner: to.clone(),
+            height: env.block.height,
+        }],
+    };
+    TOKEN_HISTORY.save(storage, id.u128(), &history)?;
+    BURNED.remove(storage, id.u128());
+
+    Ok(NftEvent::Mint {
+        owner: to,
+        token_id: id,
     })
 }
 
-fn _burn(storage: &mut dyn Storage, env: Env, from: String) -> Result<WasmMsg, ContractError> {
-    if from == "" {
+/// Burns the first `count` NFTs of the burn queue of `from`, skipping locked NFTs
+fn _burn(
+    storage: &mut dyn Storage,
+    env: &Env,
+    from: String,
+    count: u128,
+) -> Result<Vec<NftEvent>, ContractError> {
+    if from.is_empty() {
         return Err(ContractError::InvalidSender {});
     }
 
-    let mut owned = OWNED.may_load(storage, from.clone())?.unwrap_or(vec![]);
-    let id = owned[owned.len() - 1];
-    owned.pop();
-    OWNED.save(storage, from.clone(), &owned)?;
-    OWNED_INDEX.remove(storage, id.to_string());
-    OWNER_OF.remove(storage, id.to_string());
-    GET_APPROVED.remove(storage, id.to_string());
-
-    // Prevents burning if user has locked their token
-    let locked = LOCKED.may_load(storage, id.to_string())?.unwrap_or(false);
-    if locked {
-        return Err(ContractError::PreventBurn {});
+    // Locked NFTs are never burnt, the burn only fails when too few are left unlocked
+    let owned = owned_count(storage, &from)?;
+    let queue = burn_queue(storage, &env.block, &from, count.min(owned) as usize)?;
+    if (queue.len() as u128) < count.min(owned) {
+        return Err(ContractError::AllTokensLocked { address: from });
     }
 
-    Ok(WasmMsg::Execute {
-        contract_addr: env.contract.address.to_string(),
-        msg: to_json_binary(&ExecuteMsg::GenerateNftBurnEvent {
-            sender: from,
+    let mut events = vec![];
+    for id in queue {
+        _destroy(storage, &env.block, id)?;
+        events.push(NftEvent::Burn {
+            owner: from.clone(),
             token_id: id,
-        })?,
-        funds: vec![],
-    })
+        });
+    }
+    Ok(events)
 }
 
-/**
+/*
  * Additional functions to generate and emit events below
  */
 
+/// An NFT change, reported as a `wasm-cw721_*` event on the response
+enum NftEvent {
+    Mint {
+        owner: String,
+        token_id: Uint128,
+    },
+    Burn {
+        owner: String,
+        token_id: Uint128,
+    },
+    Transfer {
+        sender: String,
+        recipient: String,
+        token_id: Uint128,
+    },
+}
+
+impl NftEvent {
+    fn into_event(self, contract: &Addr) -> Event {
+        match self {
+            NftEvent::Mint { owner, token_id } => Event::new("cw721_mint")
+                .add_attribute("minter", contract.to_string())
+                .add_attribute("owner", owner)
+                .add_attribute("token_id", token_id),
+            NftEvent::Burn { owner, token_id } => Event::new("cw721_burn")
+                .add_attribute("owner", owner)
+                .add_attribute("token_id", token_id),
+            NftEvent::Transfer {
+                sender,
+                recipient,
+                token_id,
+            } => Event::new("cw721_transfer")
+                .add_attribute("sender", sender)
+                .add_attribute("recipient", recipient)
+                .add_attribute("token_id", token_id),
+        }
+    }
+
+    /// The self-dispatched message that reported this change before inline events
+    fn legacy_msg(&self, contract: &Addr) -> StdResult<WasmMsg> {
+        let msg = match self {
+            NftEvent::Mint { owner, token_id } => ExecuteMsg::GenerateNftMintEvent {
+                sender: contract.to_string(),
+                recipient: owner.clone(),
+                token_id: *token_id,
+            },
+            NftEvent::Burn { owner, token_id } => ExecuteMsg::GenerateNftBurnEvent {
+                sender: owner.clone(),
+                token_id: *token_id,
+            },
+            NftEvent::Transfer {
+                sender,
+                recipient,
+                token_id,
+            } => ExecuteMsg::GenerateNftEvent {
+                sender: sender.clone(),
+                recipient: recipient.clone(),
+                token_id: *token_id,
+            },
+        };
+        Ok(WasmMsg::Execute {
+            contract_addr: contract.to_string(),
+            msg: to_json_binary(&msg)?,
+            funds: vec![],
+        })
+    }
+}
+
+/// Attaches `events` to `res`, along with the legacy event messages when they are enabled
+fn add_nft_events(
+    storage: &dyn Storage,
+    env: &Env,
+    mut res: Response,
+    events: Vec<NftEvent>,
+) -> StdResult<Response> {
+    let contract = &env.contract.address;
+    if LEGACY_NFT_EVENTS.may_load(storage)?.unwrap_or(false) {
+        for event in &events {
+            res = res.add_message(event.legacy_msg(contract)?);
+        }
+    }
+    Ok(res.add_events(events.into_iter().map(|event| event.into_event(contract))))
+}
+
 pub fn generate_nft_event(
     _deps: DepsMut,
     env: Env,
@@ -649,7 +2001,7 @@ pub fn generate_nft_event(
     recipient: String,
     token_id: Uint128,
 ) -> Result<Response, ContractError> {
-    if info.sender.to_string() != env.contract.address.to_string() {
+    if info.sender != env.contract.address {
         return Err(ContractError::Unauthorized {});
     }
 
@@ -669,7 +2021,7 @@ pub fn generate_nft_mint_event(
     recipient: String,
     token_id: Uint128,
 ) -> Result<Response, ContractError> {
-    if info.sender.to_string() != env.contract.address.to_string() {
+    if info.sender != env.contract.address {
         return Err(ContractError::Unauthorized {});
     }
 
@@ -688,7 +2040,7 @@ pub fn generate_nft_burn_event(
     sender: String,
     token_id: Uint128,
 ) -> Result<Response, ContractError> {
-    if info.sender.to_string() != env.contract.address.to_string() {
+    if info.sender != env.contract.address {
         return Err(ContractError::Unauthorized {});
     }
 
@@ -1,9 +1,11 @@
 pub mod contract;
+mod contract_tests;
 pub mod error;
 mod execute;
+mod migrate;
 pub mod msg;
 mod query;
 pub mod state;
 
 pub use crate::error::ContractError;
-pub use crate::msg::{ExecuteMsg, InstantiateMsg, MinterResponse, QueryMsg};
+pub use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, MinterResponse, QueryMsg};
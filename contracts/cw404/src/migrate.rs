@@ -0,0 +1,315 @@
+use cosmwasm_std::{
+    Addr, Api, DepsMut, Env, Order, Response, StdError, StdResult, Storage, Uint128,
+};
+use cw2::{set_contract_version, VersionError, CONTRACT};
+use semver::Version;
+
+use crate::contract::{CONTRACT_NAME, CONTRACT_VERSION};
+use crate::error::ContractError;
+use crate::msg::MigrateMsg;
+use crate::state::{
+    tokens, Token, ACQUIRED, ACQUIRED_COUNT, ALLOWANCE, ALLOWANCE_OWNERS, BALANCES, BASE_TOKEN_URI,
+    BURN_ORDER, BURN_RANK, DECIMALS, EXEMPT_BALANCE, LEGACY_NFT_EVENTS, LOCKED, MAX_TOKEN_ID,
+    MIGRATE_LIMIT, MIGRATING_TOKENS, MINTED, NFTS_BURNED, NFT_HOLDERS, NFT_SUPPLY, OWNED,
+    OWNED_COUNT, OWNED_INDEX, OWNER, OWNER_LOCKS, OWNER_OF, RECYCLED_IDS, RECYCLE_IDS, SUPPLY_CAP,
+    TOTAL_SUPPLY, WHITELIST, WHITELIST_COUNT,
+};
+
+pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    let new_version = parse_version(CONTRACT_VERSION)?;
+
+    // Deployments instantiated before cw2 tracking have no stored version,
+    // they are migrated as the original release of this contract
+    let from_version = match CONTRACT.may_load(deps.storage)? {
+        Some(stored) => {
+            if stored.contract != CONTRACT_NAME {
+                return Err(VersionError::WrongContract {
+                    expected: CONTRACT_NAME.to_string(),
+                    found: stored.contract,
+                }
+                .into());
+            }
+            let stored_version = parse_version(&stored.version)?;
+            if stored_version > new_version {
+                return Err(VersionError::WrongVersion {
+                    expected: format!("<= {}", CONTRACT_VERSION),
+                    found: stored.version,
+                }
+                .into());
+            }
+            Some(stored_version)
+        }
+        None => None,
+    };
+
+    // Storage migrations, oldest first
+    if from_version.is_none() {
+        migrate_legacy_state(deps.storage)?;
+    }
+    migrate_owner(deps.storage, deps.api)?;
+    migrate_allowance_owners(deps.storage)?;
+    migrate_whitelist_count(deps.storage)?;
+    migrate_exempt_balance(deps.storage)?;
+    migrate_owned_tokens(deps.storage)?;
+    migrate_nft_counters(deps.storage)?;
+    migrate_owner_locks(deps.storage)?;
+
+    // Config changes
+    if let Some(uri) = msg.base_token_uri {
+        BASE_TOKEN_URI.save(deps.storage, &uri)?;
+    }
+    if let Some(recycle_ids) = msg.recycle_ids {
+        set_recycle_ids(deps.storage, recycle_ids)?;
+    }
+    if let Some(legacy_nft_events) = msg.legacy_nft_events {
+        LEGACY_NFT_EVENTS.save(deps.storage, &legacy_nft_events)?;
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute(
+            "from_version",
+            from_version.map_or("none".to_string(), |v| v.to_string()),
+        )
+        .add_attribute("to_version", CONTRACT_VERSION)
+        .add_attribute(
+            "migrating_tokens",
+            MIGRATING_TOKENS.exists(deps.storage).to_string(),
+        ))
+}
+
+fn parse_version(version: &str) -> StdResult<Version> {
+    version
+        .parse()
+        .map_err(|err| StdError::generic_err(format!("Semver: {err}")))
+}
+
+/// Initializes the items added after the original release
+fn migrate_legacy_state(storage: &mut dyn Storage) -> StdResult<()> {
+    if RECYCLE_IDS.may_load(storage)?.is_none() {
+        RECYCLE_IDS.save(storage, &false)?;
+    }
+    if MAX_TOKEN_ID.may_load(storage)?.is_none() {
+        let decimals = DECIMALS.load(storage)?;
+        let unit = Uint128::from(10u128).pow(decimals.into());
+        let total_supply = TOTAL_SUPPLY.load(storage)?;
+        MAX_TOKEN_ID.save(storage, &(total_supply / unit))?;
+    }
+    if SUPPLY_CAP.may_load(storage)?.is_none() {
+        let total_supply = TOTAL_SUPPLY.load(storage)?;
+        SUPPLY_CAP.save(storage, &total_supply)?;
+    }
+    // Indexers of the original release listen to the self-dispatched events
+    if LEGACY_NFT_EVENTS.may_load(storage)?.is_none() {
+        LEGACY_NFT_EVENTS.save(storage, &true)?;
+    }
+    Ok(())
+}
+
+/// Indexes the existing allowances by spender if they are not indexed yet
+fn migrate_allowance_owners(storage: &mut dyn Storage) -> StdResult<()> {
+    if ALLOWANCE_OWNERS.is_empty(storage) {
+        let keys = ALLOWANCE
+            .keys(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for (owner, spender) in keys {
+            ALLOWANCE_OWNERS.save(storage, (spender, owner), &true)?;
+        }
+    }
+    Ok(())
+}
+
+/// Moves the plain owner item onto cw_ownable if it is still present
+fn migrate_owner(storage: &mut dyn Storage, api: &dyn Api) -> StdResult<()> {
+    if let Some(owner) = OWNER.may_load(storage)? {
+        cw_ownable::initialize_owner(storage, api, Some(&owner))?;
+        OWNER.remove(storage);
+    }
+    Ok(())
+}
+
+/// Counts the whitelisted addresses if the count is not tracked yet
+fn migrate_whitelist_count(storage: &mut dyn Storage) -> StdResult<()> {
+    if WHITELIST_COUNT.may_load(storage)?.is_none() {
+        let mut count = 0u64;
+        for item in WHITELIST.range(storage, None, None, Order::Ascending) {
+            let (_, whitelisted) = item?;
+            if whitelisted {
+                count += 1;
+            }
+        }
+        WHITELIST_COUNT.save(storage, &count)?;
+    }
+    Ok(())
+}
+
+/// Sums the balances of whitelisted addresses if the sum is not tracked yet
+fn migrate_exempt_balance(storage: &mut dyn Storage) -> StdResult<()> {
+    if EXEMPT_BALANCE.may_load(storage)?.is_none() {
+        let mut exempt = Uint128::zero();
+        for item in WHITELIST.range(storage, None, None, Order::Ascending) {
+            let (address, whitelisted) = item?;
+            if whitelisted {
+                exempt += BALANCES
+                    .may_load(storage, &Addr::unchecked(address))?
+                    .unwrap_or_default();
+            }
+        }
+        EXEMPT_BALANCE.save(storage, &exempt)?;
+    }
+    Ok(())
+}
+
+/// Starts moving the owned lists onto `tokens()` if they are still present, and moves
+/// the next batch of NFTs
+fn migrate_owned_tokens(storage: &mut dyn Storage) -> StdResult<()> {
+    if !MIGRATING_TOKENS.exists(storage) {
+        let owned = OWNED.keys(storage, None, None, Order::Ascending).next();
+        let owner_of = OWNER_OF.keys(storage, None, None, Order::Ascending).next();
+        if owned.is_none() && owner_of.is_none() {
+            return Ok(());
+        }
+
+        // Ids owned since before acquisitions were tracked take their index in the owned
+        // list as sequence, every index is below the number of ids minted
+        let offset = MINTED.load(storage)?.u128() as u64;
+        let seq = ACQUIRED_COUNT.may_load(storage)?.unwrap_or_default();
+        ACQUIRED_COUNT.save(storage, &(offset + seq))?;
+        MIGRATING_TOKENS.save(storage, &offset)?;
+
+        // Counted as the NFTs are moved
+        NFT_SUPPLY.save(storage, &0)?;
+        NFT_HOLDERS.save(storage, &0)?;
+        NFTS_BURNED.save(storage, &0)?;
+    }
+    migrate_token_batch(storage)?;
+    Ok(())
+}
+
+/// Moves the next `MIGRATE_LIMIT` NFTs onto `tokens()`, then drops the emptied owned lists.
+/// Moved entries are removed, so what is left of the old maps is the progress of the move.
+/// Returns the number of NFTs moved and whether the move is done
+pub fn migrate_token_batch(storage: &mut dyn Storage) -> StdResult<(usize, bool)> {
+    let offset = match MIGRATING_TOKENS.may_load(storage)? {
+        Some(offset) => offset,
+        None => return Ok((0, true)),
+    };
+
+    let order = BURN_ORDER.may_load(storage)?.unwrap_or_default();
+    let batch = OWNER_OF
+        .range(storage, None, None, Order::Ascending)
+        .take(MIGRATE_LIMIT)
+        .collect::<StdResult<Vec<_>>>()?;
+    let mut supply = NFT_SUPPLY.load(storage)?;
+    let mut holders = NFT_HOLDERS.load(storage)?;
+    for (key, owner) in batch.iter() {
+        let id: Uint128 = key.parse()?;
+        let seq = match ACQUIRED.may_load(storage, key.clone())? {
+            Some(seq) => offset + seq,
+            None => {
+                let index = OWNED_INDEX.may_load(storage, key.clone())?;
+                index.unwrap_or_default().u128() as u64 + 1
+            }
+        };
+        let token = Token {
+            owner: owner.clone(),
+            burn_key: order.key(seq, id.u128()),
+            burn_rank: BURN_RANK.may_load(storage, key.clone())?,
+        };
+        tokens().save(storage, id.u128(), &token)?;
+        if LOCKED.may_load(storage, key.clone())?.unwrap_or_default() {
+            OWNER_LOCKS.save(storage, (owner.clone(), id.u128()), &true)?;
+        }
+
+        let owned = OWNED_COUNT
+            .may_load(storage, owner.clone())?
+            .unwrap_or_default();
+        if owned == 0 {
+            holders += 1;
+        }
+        OWNED_COUNT.save(storage, owner.clone(), &(owned + 1))?;
+        supply += 1;
+
+        OWNER_OF.remove(storage, key.clone());
+        OWNED_INDEX.remove(storage, key.clone());
+        ACQUIRED.remove(storage, key.clone());
+        BURN_RANK.remove(storage, key.clone());
+    }
+    NFT_SUPPLY.save(storage, &supply)?;
+    NFT_HOLDERS.save(storage, &holders)?;
+
+    // The lists themselves go once every NFT is moved
+    let left = MIGRATE_LIMIT - batch.len();
+    let owners = OWNED
+        .keys(storage, None, None, Order::Ascending)
+        .take(left)
+        .collect::<StdResult<Vec<_>>>()?;
+    for owner in owners.iter() {
+        OWNED.remove(storage, owner.clone());
+    }
+
+    let done = owners.len() < left;
+    if done {
+        let minted = MINTED.load(storage)?.u128() as u64;
+        NFTS_BURNED.save(storage, &minted.saturating_sub(supply))?;
+        MIGRATING_TOKENS.remove(storage);
+    }
+    Ok((batch.len(), done))
+}
+
+/// Counts the live NFTs and their holders if they are not tracked yet. Burns are
+/// estimated as the ids minted but no longer live, recycled ids count once
+fn migrate_nft_counters(storage: &mut dyn Storage) -> StdResult<()> {
+    if NFT_SUPPLY.may_load(storage)?.is_none() {
+        let supply = tokens().keys(storage, None, None, Order::Ascending).count() as u64;
+        let holders = OWNED_COUNT
+            .keys(storage, None, None, Order::Ascending)
+            .count() as u64;
+        let minted = MINTED.load(storage)?.u128() as u64;
+        NFT_SUPPLY.save(storage, &supply)?;
+        NFT_HOLDERS.save(storage, &holders)?;
+        NFTS_BURNED.save(storage, &minted.saturating_sub(supply))?;
+    }
+    Ok(())
+}
+
+/// Indexes the locked tokens by owner if they are not indexed yet. Tokens still kept in
+/// the owned lists are indexed as they are moved onto `tokens()`
+fn migrate_owner_locks(storage: &mut dyn Storage) -> StdResult<()> {
+    if OWNER_LOCKS.is_empty(storage) {
+        let locked = LOCKED
+            .range(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for (key, state) in locked {
+            let id: Uint128 = key.parse()?;
+            if let Some(token) = tokens().may_load(storage, id.u128())? {
+                if state {
+                    OWNER_LOCKS.save(storage, (token.owner, id.u128()), &true)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Toggles id recycling. Enabling it queues every id up to `MAX_TOKEN_ID` that is not
+/// currently owned, so the ids burnt while recycling was off are not lost. Ids minted
+/// above it are retired when burnt
+fn set_recycle_ids(storage: &mut dyn Storage, state: bool) -> Result<(), ContractError> {
+    let current = RECYCLE_IDS.may_load(storage)?.unwrap_or(false);
+    if state && !current {
+        let minted = MINTED.load(storage)?;
+        let max_token_id = MAX_TOKEN_ID.load(storage)?;
+
+        while RECYCLED_IDS.pop_front(storage)?.is_some() {}
+        for id in 1..=minted.min(max_token_id).u128() {
+            if !tokens().has(storage, id) && !OWNER_OF.has(storage, id.to_string()) {
+                RECYCLED_IDS.push_back(storage, &Uint128::from(id))?;
+            }
+        }
+    }
+    RECYCLE_IDS.save(storage, &state)?;
+    Ok(())
+}
@@ -1,7 +1,12 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Binary, Uint128};
+use cosmwasm_std::{Binary, Coin, Uint128};
+use cw20::Logo;
 use cw_utils::Expiration;
 
+use crate::state::{
+    BurnOrder, BurnRecord, Extension, Metadata, RarityTier, RerollFee, TokenHistory,
+};
+
 #[cw_serde]
 pub struct InstantiateMsg {
     // Name of the NFT contract
@@ -10,13 +15,45 @@ pub struct InstantiateMsg {
     pub symbol: String,
     // Decimals of erc404 token
     pub decimals: u8,
-    // Supply of NFTs max
+    // Supply of NFTs max, also caps the fungible supply the minter can reach
     pub total_native_supply: Uint128,
+    // Supply of NFTs minted to the instantiator at launch, defaults to total_native_supply
+    pub initial_native_supply: Option<Uint128>,
 
-    // The minter is the only one who can create new NFTs.
-    // This is designed for a base NFT that is controlled by an external program
-    // or contract. You will likely replace this with custom logic in custom NFTs
+    // The minter is the only one who can create new tokens after launch, up to
+    // total_native_supply. Each whole token minted creates an NFT for the recipient
     pub minter: Option<String>,
+
+    // Reuse the ids of burnt NFTs for new mints, keeping every id within
+    // 1..=total_native_supply. Unset or false keeps minting ever increasing ids
+    pub recycle_ids: Option<bool>,
+
+    // Order in which NFTs are burnt when a holder drops below a whole token, defaults to lifo
+    pub burn_order: Option<BurnOrder>,
+
+    // cw20 marketing info of the fungible token
+    pub marketing: Option<InstantiateMarketingInfo>,
+}
+
+#[cw_serde]
+pub struct InstantiateMarketingInfo {
+    pub project: Option<String>,
+    pub description: Option<String>,
+    // Address allowed to update the marketing info and the logo, separate from the owner
+    pub marketing: Option<String>,
+    pub logo: Option<Logo>,
+}
+
+#[cw_serde]
+pub struct MigrateMsg {
+    // Replaces the base token uri when set
+    pub base_token_uri: Option<String>,
+    // Enables or disables recycling of burnt token ids when set.
+    // Enabling queues every id that is not currently owned
+    pub recycle_ids: Option<bool>,
+    // Enables or disables the legacy `GenerateNft*Event` sub-messages when set.
+    // Deployments from before cw2 tracking keep them enabled by default
+    pub legacy_nft_events: Option<bool>,
 }
 
 // This is like Cw721ExecuteMsg but we add a Mint command for an owner
@@ -24,12 +61,19 @@ pub struct InstantiateMsg {
 // use other control logic in any contract that inherits this.
 #[cw_serde]
 pub enum ExecuteMsg {
-    // Transfer is a base message to move a token to another account without triggering actions
+    // Moves amount of fungible tokens from owner to recipient using the sender's allowance
     TransferFrom {
         owner: String,
         recipient: String,
         amount: Uint128,
     },
+    // Moves the given NFT from owner to recipient. Requires the sender to be the owner,
+    // an approved spender of the token or an operator of the owner
+    TransferNftFrom {
+        owner: String,
+        recipient: String,
+        token_id: Uint128,
+    },
     Transfer {
         recipient: String,
         amount: Uint128,
@@ -48,11 +92,40 @@ pub enum ExecuteMsg {
         token_id: Uint128,
         msg: Binary,
     },
+    // Transfers every token in token_ids to recipient, all or nothing
+    BatchTransferNft {
+        recipient: String,
+        token_ids: Vec<Uint128>,
+    },
+    // Sends every token in token_ids to contract, which receives one ReceiveNft per token
+    BatchSendNft {
+        contract: String,
+        token_ids: Vec<Uint128>,
+        msg: Binary,
+    },
+    // Transfers each (recipient, amount) pair from the sender's balance, all or nothing
+    MultiTransfer {
+        transfers: Vec<(String, Uint128)>,
+    },
+    // Burns the sender's tokens in token_ids after all their other tokens, in the given order.
+    // Replaces the previous list, an empty list falls back to the collection burn order
+    SetBurnOrder {
+        token_ids: Vec<Uint128>,
+    },
+    // Adds amount to the allowance of spender over the sender's balance.
+    // If expiration is set, it overwrites the previous expiration
     IncreaseAllowance {
         spender: String,
         amount: Uint128,
         expires: Option<Expiration>,
     },
+    // Lowers the allowance of spender by amount, removing it once it reaches zero.
+    // If expiration is set, it overwrites the previous expiration
+    DecreaseAllowance {
+        spender: String,
+        amount: Uint128,
+        expires: Option<Expiration>,
+    },
     // Allows operator to transfer / send the token from the owner's account.
     // If expiration is set, then this allowance has a time/height limit
     Approve {
@@ -60,6 +133,11 @@ pub enum ExecuteMsg {
         token_id: Uint128,
         expires: Option<Expiration>,
     },
+    // Remove previously granted Approval
+    Revoke {
+        spender: String,
+        token_id: Uint128,
+    },
     // Allows operator to transfer / send any token from the owner's account.
     // If expiration is set, then this allowance has a time/height limit
     ApproveAll {
@@ -70,6 +148,19 @@ pub enum ExecuteMsg {
     RevokeAll {
         operator: String,
     },
+    // Destroys amount from the sender's balance, burning NFTs for every whole unit lost
+    Burn {
+        amount: Uint128,
+    },
+    // Like Burn, but spends the sender's allowance over the owner's balance
+    BurnFrom {
+        owner: String,
+        amount: Uint128,
+    },
+    // Destroys the given NFT together with the unit of balance backing it
+    BurnNft {
+        token_id: Uint128,
+    },
     GenerateNftEvent {
         sender: String,
         recipient: String,
@@ -88,13 +179,76 @@ pub enum ExecuteMsg {
         target: String,
         state: bool,
     },
+    // Lets any holder opt its own address in or out of NFTs, like being whitelisted
+    SetSelfNftExempt {
+        state: bool,
+    },
+    // Mints the next batch of NFTs of an address leaving the whitelist. Leaving the whitelist
+    // mints one NFT per whole token in batches, the address stays whitelisted until the last one
+    ReconcileNfts {
+        address: String,
+    },
+    // Moves the next batch of NFTs off the owned lists of an upgraded deployment. Other
+    // messages are refused until the last batch is moved
+    MigrateTokens {},
     SetLock {
         token_id: Uint128,
         state: bool,
     },
+    // Locks or unlocks every token in token_ids. A lock with until set lifts itself once
+    // it expires. Callable by the owner of the tokens or an operator of the owner
+    SetLocks {
+        token_ids: Vec<Uint128>,
+        state: bool,
+        until: Option<Expiration>,
+    },
     SetBaseTokenUri {
         uri: String,
     },
+    // Stores the on-chain metadata of a token id, or clears it when unset.
+    // Ids that are not minted yet can be prepared ahead of time
+    SetMetadata {
+        token_id: Uint128,
+        metadata: Option<Metadata>,
+    },
+    // Creates amount of fungible tokens for recipient, minting NFTs for every whole unit gained.
+    // Only callable by the minter, and the total supply cannot exceed the cap
+    Mint {
+        recipient: String,
+        amount: Uint128,
+    },
+    // Replaces the minter, or removes it when unset.
+    // Only callable by the current minter or the contract owner
+    UpdateMinter {
+        new_minter: Option<String>,
+    },
+    // Burns the given NFT of the sender and mints a new one to them, paying the reroll fee if set
+    Reroll {
+        token_id: Uint128,
+    },
+    // Sets the native fee charged by Reroll and the address it is sent to, or removes it when
+    // unset or zero
+    SetRerollFee {
+        fee: Option<Coin>,
+        recipient: Option<String>,
+    },
+    // Replaces the rarity tiers seeds are mapped to, in order. Weights must add up to
+    // RARITY_TOTAL_WEIGHT, an empty list removes the tiers
+    SetRarityTiers {
+        tiers: Vec<RarityTier>,
+    },
+    // Transfer, accept or renounce the contract ownership, see cw_ownable::Action
+    UpdateOwnership(cw_ownable::Action),
+
+    // Only with the marketing role. Updates the cw20 marketing info, an empty string clears
+    // a field, an empty marketing address removes the role for good
+    UpdateMarketing {
+        project: Option<String>,
+        description: Option<String>,
+        marketing: Option<String>,
+    },
+    // Only with the marketing role. Replaces the logo, embedded logos are limited to 5 KB
+    UploadLogo(Logo),
 }
 
 #[cw_serde]
@@ -107,18 +261,98 @@ pub enum QueryMsg {
         // unset or false will filter out expired approvals, you must set to true to see them
         include_expired: Option<bool>,
     },
+    // Return the approval of a given spender on the given token, error if not set
+    #[returns(cw721::ApprovalResponse)]
+    Approval {
+        token_id: String,
+        spender: String,
+        include_expired: Option<bool>,
+    },
+    // Return approvals that a token has
+    #[returns(cw721::ApprovalsResponse)]
+    Approvals {
+        token_id: String,
+        include_expired: Option<bool>,
+    },
+    // Return approval of a given operator for all tokens of an owner, error if not set
+    #[returns(cw721::OperatorResponse)]
+    Operator {
+        owner: String,
+        operator: String,
+        include_expired: Option<bool>,
+    },
+    // List all operators that can access all of the owner's tokens
+    #[returns(cw721::OperatorsResponse)]
+    AllOperators {
+        owner: String,
+        // unset or false will filter out expired items, you must set to true to see them
+        include_expired: Option<bool>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
     #[returns(bool)]
     IsLocked { token_id: String },
+    // Whether address holds its balance without NFTs, either whitelisted or self-exempt
+    #[returns(bool)]
+    IsNftExempt { address: String },
+    // Lists the locked tokens of owner, ordered by token id
+    #[returns(cw721::TokensResponse)]
+    LockedTokens {
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
     #[returns(cw721::OwnerOfResponse)]
     UserInfo { address: String },
 
     #[returns(cw20::AllowanceResponse)]
     Allowance { owner: String, spender: String },
+    // Lists the allowances owner has given, with their expirations
+    #[returns(cw20::AllAllowancesResponse)]
+    AllAllowances {
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    // Lists the allowances spender has received, with their expirations
+    #[returns(cw20::AllSpenderAllowancesResponse)]
+    AllSpenderAllowances {
+        spender: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    // Lists every account that has held a balance
+    #[returns(cw20::AllAccountsResponse)]
+    AllAccounts {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
 
     #[returns(ExtendedInfoResponse)]
     ExtendedInfo { token_id: String },
 
-    // Total number of tokens issued
+    // Return the seed of the given token and its rarity tier, error if token does not exist
+    #[returns(TokenSeedResponse)]
+    TokenSeed { token_id: String },
+
+    #[returns(Vec<RarityTier>)]
+    RarityTiers {},
+
+    #[returns(Option<RerollFee>)]
+    RerollFee {},
+
+    // Returns the provenance of the latest NFT minted with the given id and its burn record,
+    // both unset if the id was never minted or predates provenance records
+    #[returns(TokenHistoryResponse)]
+    TokenHistory { token_id: String },
+    // Lists the burnt ids that have not been minted again, ordered by token ID
+    #[returns(BurnedTokensResponse)]
+    BurnedTokens {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    // Number of NFTs that currently exist
     #[returns(cw721::NumTokensResponse)]
     NumTokens {},
 
@@ -129,7 +363,7 @@ pub enum QueryMsg {
     // With MetaData Extension.
     // Returns metadata about one particular token, based on *ERC721 Metadata JSON Schema*
     // but directly from the contract
-    #[returns(cw721::NftInfoResponse)]
+    #[returns(cw721::NftInfoResponse<Extension>)]
     NftInfo { token_id: String },
 
     #[returns(cw20::BalanceResponse)]
@@ -140,7 +374,7 @@ pub enum QueryMsg {
     // With MetaData Extension.
     // Returns the result of both `NftInfo` and `OwnerOf` as one query as an optimization
     // for clients
-    #[returns(cw721::AllNftInfoResponse)]
+    #[returns(cw721::AllNftInfoResponse<Extension>)]
     AllNftInfo {
         token_id: String,
         // unset or false will filter out expired approvals, you must set to true to see them
@@ -156,22 +390,90 @@ pub enum QueryMsg {
         limit: Option<u32>,
     },
     // With Enumerable extension.
-    // Requires pagination. Lists all token_ids controlled by the contract.
+    // Requires pagination. Lists the token_ids of all NFTs that currently exist.
     #[returns(cw721::TokensResponse)]
     AllTokens {
         start_after: Option<String>,
         limit: Option<u32>,
     },
 
-    // Return the minter
+    // Returns the next count tokens of address that are burnt when it drops below a whole token
+    #[returns(cw721::TokensResponse)]
+    NextToBurn { address: String, count: u32 },
+
+    // Return the minter, the supply cap and how much can still be minted
     #[returns(MinterResponse)]
     Minter {},
+
+    // Returns the collection settings and the number of whitelisted addresses
+    #[returns(ConfigResponse)]
+    Config {},
+    // Returns the fungible and NFT supply figures
+    #[returns(SupplyStatsResponse)]
+    SupplyStats {},
+    // Lists whitelisted and self-exempt addresses with their balances, ordered by address
+    #[returns(AllWhitelistedResponse)]
+    AllWhitelisted {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    // Return the contract owner and any pending ownership transfer
+    #[returns(cw_ownable::Ownership<cosmwasm_std::Addr>)]
+    Ownership {},
+
+    // Returns the cw20 marketing info of the fungible token
+    #[returns(cw20::MarketingInfoResponse)]
+    MarketingInfo {},
+    // Returns the embedded logo with its mime type, error if there is none
+    #[returns(cw20::DownloadLogoResponse)]
+    DownloadLogo {},
 }
 
 // Shows who can mint these tokens
 #[cw_serde]
 pub struct MinterResponse {
     pub minter: Option<String>,
+    // Maximum total supply in fractional representation
+    pub cap: Uint128,
+    // Amount that can still be minted before reaching the cap
+    pub remaining: Uint128,
+}
+
+#[cw_serde]
+pub struct ConfigResponse {
+    pub base_token_uri: Option<String>,
+    pub recycle_ids: bool,
+    pub max_token_id: Uint128,
+    pub burn_order: BurnOrder,
+    pub legacy_nft_events: bool,
+    // Number of whitelisted and self-exempt addresses
+    pub whitelisted: u64,
+}
+
+#[cw_serde]
+pub struct SupplyStatsResponse {
+    // Fungible supply in fractional representation
+    pub total_supply: Uint128,
+    // Number of NFTs that currently exist
+    pub nft_supply: u64,
+    pub nfts_minted: u64,
+    pub nfts_burned: u64,
+    // Number of addresses holding at least one NFT
+    pub nft_holders: u64,
+    // Fungible balance of whitelisted and self-exempt addresses, which back no NFTs
+    pub exempt_balance: Uint128,
+}
+
+#[cw_serde]
+pub struct WhitelistedInfo {
+    pub address: String,
+    pub balance: Uint128,
+}
+
+#[cw_serde]
+pub struct AllWhitelistedResponse {
+    pub whitelisted: Vec<WhitelistedInfo>,
 }
 
 #[cw_serde]
@@ -184,4 +486,31 @@ pub struct UserInfoResponse {
 pub struct ExtendedInfoResponse {
     pub owned_index: Uint128,
     pub owner_of: String,
+    pub seed: Option<u64>,
+    pub rarity: Option<String>,
+}
+
+#[cw_serde]
+pub struct TokenHistoryResponse {
+    pub history: Option<TokenHistory>,
+    // Set once the NFT is burnt
+    pub burned: Option<BurnRecord>,
+}
+
+#[cw_serde]
+pub struct BurnedTokenInfo {
+    pub token_id: String,
+    pub burned: BurnRecord,
+}
+
+#[cw_serde]
+pub struct BurnedTokensResponse {
+    pub tokens: Vec<BurnedTokenInfo>,
+}
+
+#[cw_serde]
+pub struct TokenSeedResponse {
+    pub seed: u64,
+    // Name of the rarity tier the seed falls into, unset if no tiers are configured
+    pub rarity: Option<String>,
 }
@@ -1,18 +1,32 @@
-use cw20::{AllowanceResponse, BalanceResponse, TokenInfoResponse};
+use cw20::{
+    AllAccountsResponse, AllAllowancesResponse, AllSpenderAllowancesResponse, AllowanceInfo,
+    AllowanceResponse, BalanceResponse, DownloadLogoResponse, EmbeddedLogo, Logo,
+    MarketingInfoResponse, SpenderAllowanceInfo, TokenInfoResponse,
+};
 
-use cosmwasm_std::{to_json_binary, Binary, Deps, Env, StdResult, Uint128};
+use cosmwasm_std::{to_json_binary, Addr, Binary, Deps, Env, Order, StdError, StdResult, Uint128};
 
 use cw721::{
-    AllNftInfoResponse, Approval, ContractInfoResponse, NftInfoResponse, NumTokensResponse,
-    OwnerOfResponse, TokensResponse,
+    AllNftInfoResponse, Approval, ApprovalResponse, ApprovalsResponse, ContractInfoResponse,
+    NftInfoResponse, NumTokensResponse, OperatorResponse, OperatorsResponse, OwnerOfResponse,
+    TokensResponse,
 };
-
+use cw_storage_plus::Bound;
 use cw_utils::Expiration;
 
-use crate::msg::{ExtendedInfoResponse, MinterResponse, QueryMsg, UserInfoResponse};
+use crate::execute::{burn_queue, is_token_locked};
+use crate::msg::{
+    AllWhitelistedResponse, BurnedTokenInfo, BurnedTokensResponse, ConfigResponse,
+    ExtendedInfoResponse, MinterResponse, QueryMsg, SupplyStatsResponse, TokenHistoryResponse,
+    TokenSeedResponse, UserInfoResponse, WhitelistedInfo,
+};
 use crate::state::{
-    ALLOWANCE, BALANCES, BASE_TOKEN_URI, DECIMALS, GET_APPROVED, LOCKED, MINTED, NAME, OWNED,
-    OWNED_INDEX, OWNER_OF, SYMBOL, TOTAL_SUPPLY,
+    self, token_owner, Extension, RarityTier, ALLOWANCE, ALLOWANCE_EXPIRES, ALLOWANCE_OWNERS,
+    APPROVED_FOR_ALL, APPROVED_FOR_ALL_EXPIRES, BALANCES, BASE_TOKEN_URI, BURNED, BURN_ORDER,
+    DECIMALS, EXEMPT_BALANCE, GET_APPROVED, GET_APPROVED_EXPIRES, LEGACY_NFT_EVENTS, LOGO,
+    MARKETING_INFO, MAX_TOKEN_ID, METADATA, MINTER, NAME, NFTS_BURNED, NFT_HOLDERS, NFT_SUPPLY,
+    OWNER_LOCKS, RARITY_TIERS, RARITY_TOTAL_WEIGHT, RECYCLE_IDS, REROLL_FEE, SEED, SUPPLY_CAP,
+    SYMBOL, TOKEN_HISTORY, TOTAL_SUPPLY, WHITELIST, WHITELIST_COUNT,
 };
 
 const DEFAULT_LIMIT: u32 = 10;
@@ -25,41 +39,171 @@ fn contract_info(deps: Deps) -> StdResult<ContractInfoResponse> {
 }
 
 fn num_tokens(deps: Deps) -> StdResult<NumTokensResponse> {
-    let count = MINTED.may_load(deps.storage)?.unwrap_or(Uint128::zero());
-    Ok(NumTokensResponse {
-        count: count.u128() as u64,
-    })
+    let count = NFT_SUPPLY.may_load(deps.storage)?.unwrap_or_default();
+    Ok(NumTokensResponse { count })
 }
 
-fn nft_info(deps: Deps, token_id: String) -> StdResult<NftInfoResponse> {
-    let base_uri = BASE_TOKEN_URI
-        .may_load(deps.storage)?
-        .unwrap_or("".to_string());
+fn nft_info(deps: Deps, token_id: String) -> StdResult<NftInfoResponse<Extension>> {
+    let base_uri = BASE_TOKEN_URI.may_load(deps.storage)?.unwrap_or_default();
+    let extension = METADATA.may_load(deps.storage, token_id.clone())?;
     Ok(NftInfoResponse {
         token_uri: Some(base_uri + &token_id),
-        extension: None,
+        extension,
     })
 }
 
+fn token_approvals(
+    deps: Deps,
+    env: &Env,
+    token_id: String,
+    include_expired: bool,
+) -> StdResult<Vec<Approval>> {
+    let spender = GET_APPROVED
+        .may_load(deps.storage, token_id.clone())?
+        .unwrap_or_default();
+    let expires = GET_APPROVED_EXPIRES
+        .may_load(deps.storage, token_id)?
+        .unwrap_or_default();
+    if spender.is_empty() || (!include_expired && expires.is_expired(&env.block)) {
+        return Ok(vec![]);
+    }
+
+    Ok(vec![Approval { spender, expires }])
+}
+
+/// Owner of `token_id`, empty if it is not a live NFT
+fn load_owner(deps: Deps, token_id: &str) -> StdResult<String> {
+    match token_id.parse::<Uint128>() {
+        Ok(id) => token_owner(deps.storage, id),
+        Err(_) => Ok(String::new()),
+    }
+}
+
 fn owner_of(
     deps: Deps,
-    _env: Env,
+    env: Env,
     token_id: String,
-    _include_expired: bool,
+    include_expired: bool,
 ) -> StdResult<OwnerOfResponse> {
-    let owner = OWNER_OF
-        .may_load(deps.storage, token_id)?
-        .unwrap_or("".to_string());
-    Ok(OwnerOfResponse {
-        owner,
-        approvals: vec![],
+    let owner = load_owner(deps, &token_id)?;
+    let approvals = token_approvals(deps, &env, token_id, include_expired)?;
+    Ok(OwnerOfResponse { owner, approvals })
+}
+
+fn approval(
+    deps: Deps,
+    env: Env,
+    token_id: String,
+    spender: String,
+    include_expired: bool,
+) -> StdResult<ApprovalResponse> {
+    let owner = load_owner(deps, &token_id)?;
+    if owner.is_empty() {
+        return Err(StdError::not_found("Token"));
+    }
+
+    // token owner has absolute approval
+    if owner == spender {
+        let approval = Approval {
+            spender: owner,
+            expires: Expiration::Never {},
+        };
+        return Ok(ApprovalResponse { approval });
+    }
+
+    let approval = token_approvals(deps, &env, token_id, include_expired)?
+        .into_iter()
+        .find(|a| a.spender == spender)
+        .ok_or_else(|| StdError::not_found("Approval not found"))?;
+
+    Ok(ApprovalResponse { approval })
+}
+
+fn approvals(
+    deps: Deps,
+    env: Env,
+    token_id: String,
+    include_expired: bool,
+) -> StdResult<ApprovalsResponse> {
+    if load_owner(deps, &token_id)?.is_empty() {
+        return Err(StdError::not_found("Token"));
+    }
+    let approvals = token_approvals(deps, &env, token_id, include_expired)?;
+    Ok(ApprovalsResponse { approvals })
+}
+
+fn operator(
+    deps: Deps,
+    env: Env,
+    owner: String,
+    operator: String,
+    include_expired: bool,
+) -> StdResult<OperatorResponse> {
+    let approved = APPROVED_FOR_ALL
+        .may_load(deps.storage, (owner.clone(), operator.clone()))?
+        .unwrap_or(false);
+    let expires = APPROVED_FOR_ALL_EXPIRES
+        .may_load(deps.storage, (owner, operator.clone()))?
+        .unwrap_or_default();
+    if !approved || (!include_expired && expires.is_expired(&env.block)) {
+        return Err(StdError::not_found("Approval not found"));
+    }
+
+    Ok(OperatorResponse {
+        approval: Approval {
+            spender: operator,
+            expires,
+        },
     })
 }
 
+fn all_operators(
+    deps: Deps,
+    env: Env,
+    owner: String,
+    include_expired: bool,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<OperatorsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let operators = APPROVED_FOR_ALL
+        .prefix(owner.clone())
+        .range(deps.storage, start, None, Order::Ascending)
+        .filter_map(|item| match item {
+            Ok((_, false)) => None,
+            Ok((operator, true)) => {
+                let expires = match APPROVED_FOR_ALL_EXPIRES
+                    .may_load(deps.storage, (owner.clone(), operator.clone()))
+                {
+                    Ok(expires) => expires.unwrap_or_default(),
+                    Err(err) => return Some(Err(err)),
+                };
+                if !include_expired && expires.is_expired(&env.block) {
+                    return None;
+                }
+                Some(Ok(Approval {
+                    spender: operator,
+                    expires,
+                }))
+            }
+            Err(err) => Some(Err(err)),
+        })
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(OperatorsResponse { operators })
+}
+
 fn user_info(deps: Deps, _env: Env, address: String) -> StdResult<UserInfoResponse> {
-    let owned = OWNED
-        .may_load(deps.storage, address.clone())?
-        .unwrap_or(vec![]);
+    let owned = state::tokens()
+        .idx
+        .owner
+        .prefix(address.clone())
+        .keys(deps.storage, None, None, Order::Ascending)
+        .map(|id| id.map(Uint128::from))
+        .collect::<StdResult<Vec<_>>>()?;
     let balances = BALANCES
         .may_load(deps.storage, &deps.api.addr_validate(&address)?)?
         .unwrap_or(Uint128::zero());
@@ -67,18 +211,102 @@ fn user_info(deps: Deps, _env: Env, address: String) -> StdResult<UserInfoRespon
 }
 
 fn extended_info(deps: Deps, _env: Env, token_id: String) -> StdResult<ExtendedInfoResponse> {
-    let owned_index = OWNED_INDEX
-        .may_load(deps.storage, token_id.clone())?
-        .unwrap_or(Uint128::zero());
-    let owner_of = OWNER_OF
-        .may_load(deps.storage, token_id.clone())?
-        .unwrap_or("".to_string());
+    let owner_of = load_owner(deps, &token_id)?;
+    // Position of the id among the ids of its owner, in ascending order
+    let owned_index = match token_id.parse::<u128>() {
+        Ok(id) if !owner_of.is_empty() => state::tokens()
+            .idx
+            .owner
+            .prefix(owner_of.clone())
+            .keys(
+                deps.storage,
+                None,
+                Some(Bound::exclusive(id)),
+                Order::Ascending,
+            )
+            .count() as u128,
+        _ => 0,
+    }
+    .into();
+    let seed = SEED.may_load(deps.storage, token_id)?;
+    let rarity = match seed {
+        Some(seed) => rarity_of(deps, seed)?,
+        None => None,
+    };
     Ok(ExtendedInfoResponse {
         owned_index,
         owner_of,
+        seed,
+        rarity,
     })
 }
 
+/// Maps a seed onto the configured rarity tiers, walking their cumulative weights
+fn rarity_of(deps: Deps, seed: u64) -> StdResult<Option<String>> {
+    let tiers = RARITY_TIERS.may_load(deps.storage)?.unwrap_or_default();
+    let roll = seed % RARITY_TOTAL_WEIGHT;
+    let mut cumulative = 0;
+    for tier in tiers {
+        cumulative += tier.weight;
+        if roll < cumulative {
+            return Ok(Some(tier.name));
+        }
+    }
+    Ok(None)
+}
+
+fn token_seed(deps: Deps, _env: Env, token_id: String) -> StdResult<TokenSeedResponse> {
+    let seed = SEED
+        .may_load(deps.storage, token_id)?
+        .ok_or_else(|| StdError::not_found("Token"))?;
+    let rarity = rarity_of(deps, seed)?;
+    Ok(TokenSeedResponse { seed, rarity })
+}
+
+fn token_history(deps: Deps, token_id: String) -> StdResult<TokenHistoryResponse> {
+    let id = match token_id.parse::<u128>() {
+        Ok(id) => id,
+        Err(_) => {
+            return Ok(TokenHistoryResponse {
+                history: None,
+                burned: None,
+            })
+        }
+    };
+    Ok(TokenHistoryResponse {
+        history: TOKEN_HISTORY.may_load(deps.storage, id)?,
+        burned: BURNED.may_load(deps.storage, id)?,
+    })
+}
+
+fn burned_tokens(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<BurnedTokensResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .map(|s| s.parse::<u128>().map(Bound::exclusive))
+        .transpose()
+        .map_err(|e| StdError::parse_err("u128", e))?;
+
+    let tokens = BURNED
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            item.map(|(id, burned)| BurnedTokenInfo {
+                token_id: id.to_string(),
+                burned,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(BurnedTokensResponse { tokens })
+}
+
+fn rarity_tiers(deps: Deps) -> StdResult<Vec<RarityTier>> {
+    Ok(RARITY_TIERS.may_load(deps.storage)?.unwrap_or_default())
+}
+
 fn allowance(
     deps: Deps,
     _env: Env,
@@ -86,18 +314,141 @@ fn allowance(
     spender: String,
 ) -> StdResult<AllowanceResponse> {
     let allowance = ALLOWANCE
-        .may_load(deps.storage, (owner, spender))?
+        .may_load(deps.storage, (owner.clone(), spender.clone()))?
         .unwrap_or(Uint128::zero());
+    let expires = ALLOWANCE_EXPIRES
+        .may_load(deps.storage, (owner, spender))?
+        .unwrap_or_default();
 
-    Ok(AllowanceResponse {
-        allowance,
-        expires: Expiration::Never {},
-    })
+    Ok(AllowanceResponse { allowance, expires })
 }
 
-fn is_locked(deps: Deps, _env: Env, token_id: String) -> StdResult<bool> {
-    let locked = LOCKED.may_load(deps.storage, token_id)?.unwrap_or(false);
-    Ok(locked)
+fn all_allowances(
+    deps: Deps,
+    owner: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<AllAllowancesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let allowances = ALLOWANCE
+        .prefix(owner.clone())
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (spender, allowance) = item?;
+            let expires = ALLOWANCE_EXPIRES
+                .may_load(deps.storage, (owner.clone(), spender.clone()))?
+                .unwrap_or_default();
+            Ok(AllowanceInfo {
+                spender,
+                allowance,
+                expires,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(AllAllowancesResponse { allowances })
+}
+
+fn all_spender_allowances(
+    deps: Deps,
+    spender: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<AllSpenderAllowancesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let allowances = ALLOWANCE_OWNERS
+        .prefix(spender.clone())
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|owner| {
+            let owner = owner?;
+            let key = (owner.clone(), spender.clone());
+            let allowance = ALLOWANCE
+                .may_load(deps.storage, key.clone())?
+                .unwrap_or_default();
+            let expires = ALLOWANCE_EXPIRES
+                .may_load(deps.storage, key)?
+                .unwrap_or_default();
+            Ok(SpenderAllowanceInfo {
+                owner,
+                allowance,
+                expires,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(AllSpenderAllowancesResponse { allowances })
+}
+
+fn all_accounts(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<AllAccountsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after.map(Addr::unchecked);
+    let start = start_after.as_ref().map(Bound::exclusive);
+
+    let accounts = BALANCES
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|account| account.map(String::from))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(AllAccountsResponse { accounts })
+}
+
+fn is_locked(deps: Deps, env: Env, token_id: String) -> StdResult<bool> {
+    let token_id = token_id.parse::<Uint128>()?;
+    is_token_locked(deps.storage, &env.block, token_id)
+}
+
+fn is_nft_exempt(deps: Deps, address: String) -> StdResult<bool> {
+    let address = deps.api.addr_validate(&address)?;
+    let exempt = WHITELIST
+        .may_load(deps.storage, address.to_string())?
+        .unwrap_or_default();
+    Ok(exempt)
+}
+
+fn locked_tokens(
+    deps: Deps,
+    env: Env,
+    owner: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<TokensResponse> {
+    let owner_addr = deps.api.addr_validate(&owner)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .map(|s| s.parse::<u128>().map(Bound::exclusive))
+        .transpose()
+        .map_err(|e| StdError::parse_err("u128", e))?;
+
+    let locks = OWNER_LOCKS.prefix(owner_addr.to_string()).keys(
+        deps.storage,
+        start,
+        None,
+        Order::Ascending,
+    );
+
+    // Expired locks stay indexed until the token is unlocked, transferred or burnt
+    let mut tokens = vec![];
+    for id in locks {
+        if tokens.len() == limit {
+            break;
+        }
+        let id = Uint128::from(id?);
+        if is_token_locked(deps.storage, &env.block, id)? {
+            tokens.push(id.to_string());
+        }
+    }
+    Ok(TokensResponse { tokens })
 }
 
 fn tokens(
@@ -107,36 +458,35 @@ fn tokens(
     limit: Option<u32>,
 ) -> StdResult<TokensResponse> {
     let owner_addr = deps.api.addr_validate(&owner)?;
-    let mut owned = OWNED
-        .may_load(deps.storage, owner_addr.to_string())?
-        .unwrap();
-
-    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as u64;
-    let mut start = start_after
-        .clone()
-        .unwrap_or("0".to_string())
-        .parse::<u64>()
-        .ok()
-        .unwrap();
-    start = if start_after.is_none() { 0 } else { start };
-    let offset = if start_after.is_none() { 0 } else { 1 };
-
-    owned.sort();
-
-    let start_index = owned
-        .iter()
-        .position(|item| item.u128() as u64 == start)
-        .unwrap_or(0)
-        + offset;
-
-    let end_index = (start_index + limit as usize).min(owned.len()); // Calculate end index
-
-    let tokens = owned[start_index..end_index]
-        .to_vec()
-        .iter()
-        .map(|item| item.to_string())
-        .collect();
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .map(|s| s.parse::<u128>().map(Bound::exclusive))
+        .transpose()
+        .map_err(|e| StdError::parse_err("u128", e))?;
+
+    let tokens = state::tokens()
+        .idx
+        .owner
+        .prefix(owner_addr.to_string())
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|id| id.map(|id| id.to_string()))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(TokensResponse { tokens })
+}
 
+fn next_to_burn(deps: Deps, env: Env, address: String, count: u32) -> StdResult<TokensResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let tokens = burn_queue(
+        deps.storage,
+        &env.block,
+        address.as_str(),
+        count.min(MAX_LIMIT) as usize,
+    )?
+    .into_iter()
+    .map(|id| id.to_string())
+    .collect();
     Ok(TokensResponse { tokens })
 }
 
@@ -145,53 +495,30 @@ fn all_tokens(
     start_after: Option<String>,
     limit: Option<u32>,
 ) -> StdResult<TokensResponse> {
-    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as u64;
-    let mut start = start_after
-        .clone()
-        .unwrap_or("0".to_string())
-        .parse::<u64>()
-        .ok()
-        .unwrap();
-    start = if start_after.is_none() { 0 } else { start + 1 };
-
-    let minted = MINTED.may_load(deps.storage)?.unwrap_or(Uint128::zero());
-    let end = if start + limit >= minted.u128() as u64 {
-        minted.u128() as u64
-    } else {
-        start + limit
-    };
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .map(|s| s.parse::<u128>().map(Bound::exclusive))
+        .transpose()
+        .map_err(|e| StdError::parse_err("u128", e))?;
 
-    let mut tokens: Vec<String> = Vec::new();
-    for i in start..end {
-        tokens.push(i.to_string());
-    }
+    let tokens = state::tokens()
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|id| id.map(|id| id.to_string()))
+        .collect::<StdResult<Vec<_>>>()?;
 
     Ok(TokensResponse { tokens })
 }
 
 fn all_nft_info(
     deps: Deps,
-    _env: Env,
+    env: Env,
     token_id: String,
-    _include_expired: bool,
-) -> StdResult<AllNftInfoResponse> {
-    let owner = OWNER_OF
-        .may_load(deps.storage, token_id.clone())?
-        .unwrap_or("".to_string());
-    let spender = GET_APPROVED
-        .may_load(deps.storage, token_id.clone())?
-        .unwrap_or("".to_string());
+    include_expired: bool,
+) -> StdResult<AllNftInfoResponse<Extension>> {
+    let owner = load_owner(deps, &token_id)?;
+    let approvals = token_approvals(deps, &env, token_id.clone(), include_expired)?;
     let info = nft_info(deps, token_id)?;
-    let approvals = if spender.len() == 0 {
-        vec![]
-    } else {
-        vec![Approval {
-            /// Account that can transfer/send the token
-            spender: spender.to_string(),
-            /// When the Approval expires (maybe Expiration::never)
-            expires: Expiration::Never {},
-        }]
-    };
 
     Ok(AllNftInfoResponse {
         access: OwnerOfResponse {
@@ -207,7 +534,18 @@ fn all_nft_info(
 
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
+        QueryMsg::NextToBurn { address, count } => {
+            to_json_binary(&next_to_burn(deps, env, address, count)?)
+        }
         QueryMsg::Minter {} => to_json_binary(&minter(deps)?),
+        QueryMsg::Config {} => to_json_binary(&config(deps)?),
+        QueryMsg::SupplyStats {} => to_json_binary(&supply_stats(deps)?),
+        QueryMsg::AllWhitelisted { start_after, limit } => {
+            to_json_binary(&all_whitelisted(deps, start_after, limit)?)
+        }
+        QueryMsg::Ownership {} => to_json_binary(&cw_ownable::get_ownership(deps.storage)?),
+        QueryMsg::MarketingInfo {} => to_json_binary(&marketing_info(deps)?),
+        QueryMsg::DownloadLogo {} => to_json_binary(&download_logo(deps)?),
         QueryMsg::ContractInfo {} => to_json_binary(&contract_info(deps)?),
         QueryMsg::Balance { address } => {
             let user = deps.api.addr_validate(&address)?;
@@ -242,10 +580,80 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
         // Allows us to view state of a user
         QueryMsg::UserInfo { address } => to_json_binary(&user_info(deps, env, address)?),
         QueryMsg::ExtendedInfo { token_id } => to_json_binary(&extended_info(deps, env, token_id)?),
+        QueryMsg::TokenSeed { token_id } => to_json_binary(&token_seed(deps, env, token_id)?),
+        QueryMsg::RarityTiers {} => to_json_binary(&rarity_tiers(deps)?),
+        QueryMsg::RerollFee {} => to_json_binary(&REROLL_FEE.may_load(deps.storage)?),
+        QueryMsg::TokenHistory { token_id } => to_json_binary(&token_history(deps, token_id)?),
+        QueryMsg::BurnedTokens { start_after, limit } => {
+            to_json_binary(&burned_tokens(deps, start_after, limit)?)
+        }
         QueryMsg::Allowance { owner, spender } => {
             to_json_binary(&allowance(deps, env, owner, spender)?)
         }
+        QueryMsg::AllAllowances {
+            owner,
+            start_after,
+            limit,
+        } => to_json_binary(&all_allowances(deps, owner, start_after, limit)?),
+        QueryMsg::AllSpenderAllowances {
+            spender,
+            start_after,
+            limit,
+        } => to_json_binary(&all_spender_allowances(deps, spender, start_after, limit)?),
+        QueryMsg::AllAccounts { start_after, limit } => {
+            to_json_binary(&all_accounts(deps, start_after, limit)?)
+        }
+        QueryMsg::Approval {
+            token_id,
+            spender,
+            include_expired,
+        } => to_json_binary(&approval(
+            deps,
+            env,
+            token_id,
+            spender,
+            include_expired.unwrap_or(false),
+        )?),
+        QueryMsg::Approvals {
+            token_id,
+            include_expired,
+        } => to_json_binary(&approvals(
+            deps,
+            env,
+            token_id,
+            include_expired.unwrap_or(false),
+        )?),
+        QueryMsg::Operator {
+            owner,
+            operator: operator_addr,
+            include_expired,
+        } => to_json_binary(&operator(
+            deps,
+            env,
+            owner,
+            operator_addr,
+            include_expired.unwrap_or(false),
+        )?),
+        QueryMsg::AllOperators {
+            owner,
+            include_expired,
+            start_after,
+            limit,
+        } => to_json_binary(&all_operators(
+            deps,
+            env,
+            owner,
+            include_expired.unwrap_or(false),
+            start_after,
+            limit,
+        )?),
         QueryMsg::IsLocked { token_id } => to_json_binary(&is_locked(deps, env, token_id)?),
+        QueryMsg::IsNftExempt { address } => to_json_binary(&is_nft_exempt(deps, address)?),
+        QueryMsg::LockedTokens {
+            owner,
+            start_after,
+            limit,
+        } => to_json_binary(&locked_tokens(deps, env, owner, start_after, limit)?),
         QueryMsg::AllNftInfo {
             token_id,
             include_expired,
@@ -267,10 +675,86 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     }
 }
 
+pub fn config(deps: Deps) -> StdResult<ConfigResponse> {
+    Ok(ConfigResponse {
+        base_token_uri: BASE_TOKEN_URI.may_load(deps.storage)?,
+        recycle_ids: RECYCLE_IDS.may_load(deps.storage)?.unwrap_or(false),
+        max_token_id: MAX_TOKEN_ID.may_load(deps.storage)?.unwrap_or_default(),
+        burn_order: BURN_ORDER.may_load(deps.storage)?.unwrap_or_default(),
+        legacy_nft_events: LEGACY_NFT_EVENTS.may_load(deps.storage)?.unwrap_or(false),
+        whitelisted: WHITELIST_COUNT.may_load(deps.storage)?.unwrap_or_default(),
+    })
+}
+
+fn marketing_info(deps: Deps) -> StdResult<MarketingInfoResponse> {
+    Ok(MARKETING_INFO.may_load(deps.storage)?.unwrap_or_default())
+}
+
+fn download_logo(deps: Deps) -> StdResult<DownloadLogoResponse> {
+    let logo = LOGO.load(deps.storage)?;
+    match logo {
+        Logo::Embedded(EmbeddedLogo::Svg(data)) => Ok(DownloadLogoResponse {
+            mime_type: "image/svg+xml".to_string(),
+            data,
+        }),
+        Logo::Embedded(EmbeddedLogo::Png(data)) => Ok(DownloadLogoResponse {
+            mime_type: "image/png".to_string(),
+            data,
+        }),
+        Logo::Url(_) => Err(StdError::not_found("logo")),
+    }
+}
+
+fn supply_stats(deps: Deps) -> StdResult<SupplyStatsResponse> {
+    let nft_supply = NFT_SUPPLY.may_load(deps.storage)?.unwrap_or_default();
+    let nfts_burned = NFTS_BURNED.may_load(deps.storage)?.unwrap_or_default();
+
+    Ok(SupplyStatsResponse {
+        total_supply: TOTAL_SUPPLY.load(deps.storage)?,
+        nft_supply,
+        nfts_minted: nft_supply + nfts_burned,
+        nfts_burned,
+        nft_holders: NFT_HOLDERS.may_load(deps.storage)?.unwrap_or_default(),
+        exempt_balance: EXEMPT_BALANCE.may_load(deps.storage)?.unwrap_or_default(),
+    })
+}
+
+fn all_whitelisted(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<AllWhitelistedResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    // Addresses removed before entries were deleted on removal are still stored as false
+    let whitelisted = WHITELIST
+        .range(deps.storage, start, None, Order::Ascending)
+        .filter_map(|item| match item {
+            Ok((_, false)) => None,
+            Ok((address, true)) => {
+                let balance = match BALANCES.may_load(deps.storage, &Addr::unchecked(&address)) {
+                    Ok(balance) => balance.unwrap_or_default(),
+                    Err(err) => return Some(Err(err)),
+                };
+                Some(Ok(WhitelistedInfo { address, balance }))
+            }
+            Err(err) => Some(Err(err)),
+        })
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(AllWhitelistedResponse { whitelisted })
+}
+
 pub fn minter(deps: Deps) -> StdResult<MinterResponse> {
-    let minter = cw_ownable::get_ownership(deps.storage)?
-        .owner
-        .map(|a| a.into_string());
+    let minter = MINTER.may_load(deps.storage)?.map(|a| a.into_string());
+    let total_supply = TOTAL_SUPPLY.load(deps.storage)?;
+    let cap = SUPPLY_CAP.may_load(deps.storage)?.unwrap_or(total_supply);
 
-    Ok(MinterResponse { minter })
+    Ok(MinterResponse {
+        minter,
+        cap,
+        remaining: cap.saturating_sub(total_supply),
+    })
 }
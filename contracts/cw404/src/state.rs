@@ -1,8 +1,14 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    to_json_binary, Addr, Binary, Coin, CosmosMsg, StdResult, Storage, Timestamp, Uint128, WasmMsg,
+};
+use cw20::{Logo, MarketingInfoResponse};
+use cw_storage_plus::{Deque, Index, IndexList, IndexedMap, Item, Map, MultiIndex};
+use cw_utils::Expiration;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use cosmwasm_std::{to_json_binary, Addr, Binary, CosmosMsg, StdResult, Uint128, WasmMsg};
-use cw_storage_plus::{Item, Map};
 
+/// Owner before ownership moved to cw_ownable, only read when migrating
 pub const OWNER: Item<String> = Item::new("owner");
 
 pub const NAME: Item<String> = Item::new("name");
@@ -11,23 +17,232 @@ pub const BASE_TOKEN_URI: Item<String> = Item::new("base_token_uri");
 pub const DECIMALS: Item<u8> = Item::new("decimals");
 pub const TOTAL_SUPPLY: Item<Uint128> = Item::new("total_supply");
 pub const MINTED: Item<Uint128> = Item::new("minted");
+/// Account allowed to mint new fungible tokens after launch
+pub const MINTER: Item<Addr> = Item::new("minter");
+/// Maximum total supply in fractional representation
+pub const SUPPLY_CAP: Item<Uint128> = Item::new("supply_cap");
+/// Whether ids of burnt NFTs are handed out again by future mints
+pub const RECYCLE_IDS: Item<bool> = Item::new("recycle_ids");
+/// Whether NFT changes are also reported through the self-dispatched `GenerateNft*Event` messages
+pub const LEGACY_NFT_EVENTS: Item<bool> = Item::new("legacy_nft_events");
+/// Highest token id that can be minted while recycling is enabled
+pub const MAX_TOKEN_ID: Item<Uint128> = Item::new("max_token_id");
+/// FIFO queue of burnt ids waiting to be minted again
+pub const RECYCLED_IDS: Deque<Uint128> = Deque::new("recycled_ids");
 pub const WHITELIST: Map<String, bool> = Map::new("whitelist");
+/// Number of addresses currently whitelisted in `WHITELIST`
+pub const WHITELIST_COUNT: Item<u64> = Item::new("whitelist_count");
+/// Addresses leaving the whitelist whose NFTs are still being minted, they stay whitelisted until done
+pub const RECONCILING: Map<String, bool> = Map::new("reconciling");
+/// Most NFTs minted or burnt for one address by a single reconciliation step
+pub const RECONCILE_LIMIT: u128 = 100;
 /// Approval in native representation
 pub const GET_APPROVED: Map<String, String> = Map::new("get_approved");
+/// Expiration of the approval stored in `GET_APPROVED`
+pub const GET_APPROVED_EXPIRES: Map<String, Expiration> = Map::new("get_approved_expires");
 /// Allowance of user in fractional representation
 pub const ALLOWANCE: Map<(String, String), Uint128> = Map::new("cw20_allowance");
+/// Expiration of the allowance stored in `ALLOWANCE`
+pub const ALLOWANCE_EXPIRES: Map<(String, String), Expiration> = Map::new("cw20_allowance_expires");
+/// Owners that gave an allowance to a spender, keyed (spender, owner) to mirror `ALLOWANCE`
+pub const ALLOWANCE_OWNERS: Map<(String, String), bool> = Map::new("cw20_allowance_owners");
 pub const BALANCES: Map<&Addr, Uint128> = Map::new("balance");
-/// Owner of a tokenID in native representation
+/// Owner of a tokenID before `tokens()`, only read when migrating
 pub const OWNER_OF: Map<String, String> = Map::new("owner_of");
-/// Array of owned ids in native representation
+/// Owned ids before `tokens()`, only read when migrating
 pub const OWNED: Map<String, Vec<Uint128>> = Map::new("owned");
-/// @dev Tracks indices for the _owned mapping
+/// Index of a tokenID in `OWNED`, only read when migrating
 pub const OWNED_INDEX: Map<String, Uint128> = Map::new("owned_index");
+/// Added to the acquisition sequence of the NFTs still to be moved off the owned lists,
+/// present until every NFT is moved onto `tokens()`
+pub const MIGRATING_TOKENS: Item<u64> = Item::new("migrating_tokens");
+/// Most NFTs moved onto `tokens()` by a single migration step
+pub const MIGRATE_LIMIT: usize = 100;
+/// Acquisition sequence before `tokens()`, only read when migrating
+pub const ACQUIRED: Map<String, u64> = Map::new("acquired");
+/// Last sequence number handed out when a token id changed hands, orders LIFO and FIFO burns
+pub const ACQUIRED_COUNT: Item<u64> = Item::new("acquired_count");
+/// Order in which NFTs are burnt when a holder drops below a whole token
+pub const BURN_ORDER: Item<BurnOrder> = Item::new("burn_order");
+/// `SetBurnOrder` position before `tokens()`, only read when migrating
+pub const BURN_RANK: Map<String, u64> = Map::new("burn_rank");
+/// Number of live NFTs held by an address
+pub const OWNED_COUNT: Map<String, u64> = Map::new("owned_count");
+/// Number of NFTs that currently exist
+pub const NFT_SUPPLY: Item<u64> = Item::new("nft_supply");
+/// Number of NFTs burnt since launch
+pub const NFTS_BURNED: Item<u64> = Item::new("nfts_burned");
+/// Number of addresses holding at least one NFT
+pub const NFT_HOLDERS: Item<u64> = Item::new("nft_holders");
+/// Sum of the balances of whitelisted and self-exempt addresses
+pub const EXEMPT_BALANCE: Item<Uint128> = Item::new("exempt_balance");
 pub const APPROVED_FOR_ALL: Map<(String, String), bool> = Map::new("approved_for_all");
+/// Expiration of the operator approval stored in `APPROVED_FOR_ALL`
+pub const APPROVED_FOR_ALL_EXPIRES: Map<(String, String), Expiration> =
+    Map::new("approved_for_all_expires");
+
+/// On-chain metadata of a token id, kept across burns as it describes the artwork of the id
+pub const METADATA: Map<String, Metadata> = Map::new("metadata");
+
+/// Seed derived for a token id when it was minted
+pub const SEED: Map<String, u64> = Map::new("seed");
+/// Rarity tiers seeds are mapped to, see `RarityTier`
+pub const RARITY_TIERS: Item<Vec<RarityTier>> = Item::new("rarity_tiers");
+/// Sum of the weights of all rarity tiers
+pub const RARITY_TOTAL_WEIGHT: u64 = 10_000;
+
+/// Provenance of the latest NFT minted with a token id, kept after it is burnt
+pub const TOKEN_HISTORY: Map<u128, TokenHistory> = Map::new("token_history");
+/// Most owners kept in `TokenHistory::owners`
+pub const HISTORY_LIMIT: usize = 10;
+/// Burn record of token ids whose latest NFT was burnt, cleared when the id is minted again
+pub const BURNED: Map<u128, BurnRecord> = Map::new("burned");
+
+/// cw20 marketing info of the fungible token
+pub const MARKETING_INFO: Item<MarketingInfoResponse> = Item::new("marketing_info");
+/// Logo of the fungible token, only read back when embedded
+pub const LOGO: Item<Logo> = Item::new("logo");
+/// Largest embedded logo in bytes
+pub const LOGO_SIZE_CAP: usize = 5 * 1024;
+
+/// Fee charged by `Reroll`, unset when rerolls are free
+pub const REROLL_FEE: Item<RerollFee> = Item::new("reroll_fee");
 
 /// Additional features
 /// @dev prevents being burnt due to transfers made in mistake
 pub const LOCKED: Map<String, bool> = Map::new("locked");
+/// Expiration of the lock stored in `LOCKED`
+pub const LOCKED_UNTIL: Map<String, Expiration> = Map::new("locked_until");
+/// Locked token ids indexed by the owner who locked them
+pub const OWNER_LOCKS: Map<(String, u128), bool> = Map::new("owner_locks");
+
+#[cw_serde]
+#[derive(Default)]
+pub enum BurnOrder {
+    /// Most recently received first
+    #[default]
+    Lifo,
+    /// Earliest received first
+    Fifo,
+    LowestId,
+    HighestId,
+}
+
+impl BurnOrder {
+    /// Position in the burn queue of `token_id` received as the `seq`-th acquisition
+    pub fn key(&self, seq: u64, token_id: u128) -> u128 {
+        match self {
+            BurnOrder::Lifo => u128::MAX - u128::from(seq),
+            BurnOrder::Fifo => u128::from(seq),
+            BurnOrder::LowestId => token_id,
+            BurnOrder::HighestId => u128::MAX - token_id,
+        }
+    }
+}
+
+/// A live NFT
+#[cw_serde]
+pub struct Token {
+    pub owner: String,
+    /// Position in the collection burn order, see `BurnOrder::key`
+    pub burn_key: u128,
+    /// Position in the `SetBurnOrder` list of the owner, listed tokens burn after the others
+    pub burn_rank: Option<u64>,
+}
+
+pub struct TokenIndexes<'a> {
+    pub owner: MultiIndex<'a, String, Token, u128>,
+    /// Tokens of an owner in the order they are burnt, keyed (owner, listed, position)
+    pub burn_queue: MultiIndex<'a, (String, u8, u128), Token, u128>,
+}
+
+impl<'a> IndexList<Token> for TokenIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Token>> + '_> {
+        let v: Vec<&dyn Index<Token>> = vec![&self.owner, &self.burn_queue];
+        Box::new(v.into_iter())
+    }
+}
+
+/// Live NFTs by token id
+pub fn tokens<'a>() -> IndexedMap<'a, u128, Token, TokenIndexes<'a>> {
+    let indexes = TokenIndexes {
+        owner: MultiIndex::new(|_pk, t: &Token| t.owner.clone(), "tokens", "tokens__owner"),
+        burn_queue: MultiIndex::new(
+            |_pk, t: &Token| match t.burn_rank {
+                Some(rank) => (t.owner.clone(), 1, u128::from(rank)),
+                None => (t.owner.clone(), 0, t.burn_key),
+            },
+            "tokens",
+            "tokens__burn_queue",
+        ),
+    };
+    IndexedMap::new("tokens", indexes)
+}
+
+/// Owner of a live NFT, empty if the id is not minted
+pub fn token_owner(storage: &dyn Storage, token_id: Uint128) -> StdResult<String> {
+    Ok(tokens()
+        .may_load(storage, token_id.u128())?
+        .map(|t| t.owner)
+        .unwrap_or_default())
+}
+
+// see: https://docs.opensea.io/docs/metadata-standards
+#[cw_serde]
+#[derive(Default)]
+pub struct Trait {
+    pub display_type: Option<String>,
+    pub trait_type: String,
+    pub value: String,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct Metadata {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub image: Option<String>,
+    pub attributes: Option<Vec<Trait>>,
+}
+
+pub type Extension = Option<Metadata>;
+
+#[cw_serde]
+pub struct RarityTier {
+    pub name: String,
+    // Share of the seeds that fall into this tier, out of RARITY_TOTAL_WEIGHT
+    pub weight: u64,
+}
+
+#[cw_serde]
+pub struct TokenHistory {
+    pub minted_height: u64,
+    pub minted_time: Timestamp,
+    pub first_owner: String,
+    // Latest owners, oldest first, the first owner included until HISTORY_LIMIT is reached
+    pub owners: Vec<OwnerRecord>,
+}
+
+#[cw_serde]
+pub struct OwnerRecord {
+    pub owner: String,
+    // Height of the block the owner received the NFT in
+    pub height: u64,
+}
+
+#[cw_serde]
+pub struct BurnRecord {
+    pub height: u64,
+    pub time: Timestamp,
+    pub last_owner: String,
+}
+
+#[cw_serde]
+pub struct RerollFee {
+    pub fee: Coin,
+    // Address the fee is forwarded to
+    pub recipient: Addr,
+}
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 #[serde(rename_all = "snake_case")]
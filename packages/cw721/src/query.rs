@@ -123,21 +123,21 @@ pub struct ContractInfoResponse {
 }
 
 #[cw_serde]
-pub struct NftInfoResponse {
+pub struct NftInfoResponse<T> {
     /// Universal resource identifier for this NFT
     /// Should point to a JSON file that conforms to the ERC721
     /// Metadata JSON Schema
     pub token_uri: Option<String>,
     /// You can add any custom metadata here when you extend cw721-base
-    pub extension: Option<String>,
+    pub extension: T,
 }
 
 #[cw_serde]
-pub struct AllNftInfoResponse {
+pub struct AllNftInfoResponse<T> {
     /// Who can transfer the token
     pub access: OwnerOfResponse,
     /// Data on the token itself,
-    pub info: NftInfoResponse,
+    pub info: NftInfoResponse<T>,
 }
 
 #[cw_serde]